@@ -1,39 +1,731 @@
 use ::proc_macro::TokenStream;
-use ::quote::quote;
+use ::quote::{quote, quote_spanned, ToTokens};
 use ::syn::{
-    parse_macro_input, punctuated::Punctuated, Attribute, DeriveInput, Ident, NestedMeta, Token,
+    parse::{Parse, ParseStream},
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Data, DeriveInput, FnArg, Generics, Ident, ImplItem, ItemImpl, Lit, LitStr, Meta,
+    MetaNameValue, NestedMeta, Path, Signature, Token, Type,
 };
 
 const PUBLISHES_ATTRIBUTE: &str = "publish";
 const HANDLES_ATTRIBUTE: &str = "handle";
+const SUBSCRIBES_ATTRIBUTE: &str = "subscribe";
+const MAILBOX_ATTRIBUTE: &str = "mailbox";
+const LAG_ATTRIBUTE: &str = "lag";
+const HANDLER_ATTRIBUTE: &str = "handler";
+const ACTOR_ATTRIBUTE: &str = "actor";
+const HOOKS_OPTION: &str = "hooks";
+const VARIANT_OPTION: &str = "variant";
 
-#[proc_macro_derive(Actor, attributes(publish, handle))]
+#[proc_macro_derive(Actor, attributes(publish, handle, subscribe, mailbox, lag, actor))]
 pub fn actor_derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input);
     process_actor_derive(ast)
 }
 
 fn process_actor_derive(input: DeriveInput) -> TokenStream {
-    let publishes = get_idents(PUBLISHES_ATTRIBUTE, &input);
-    let handles = get_idents(HANDLES_ATTRIBUTE, &input);
-
     let name = &input.ident;
+    let (_, type_generics, _) = input.generics.split_for_impl();
+    let self_type = quote! { #name #type_generics };
+    let impls = get_hooks(&input.attrs).and_then(|hooks| {
+        if let Some(handle) = get_handles(&input.attrs)?.iter().find(|handle| handle.dispatch) {
+            return Err(::syn::Error::new_spanned(
+                &handle.message,
+                "a `dispatch` enum is handled by `#[handler(variant = ..)]` methods, which need an `#[actor]` impl rather than the derive",
+            ));
+        }
+        actor_impls(&input.generics, &self_type, &input.attrs, vec![], hooks)
+    });
+    match impls {
+        Ok(tokens) => TokenStream::from(tokens),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
 
-    TokenStream::from(quote! {
-        impl ::yaaf::Actor for #name {
-            type Publishes = (#(#publishes, )*);
-            type Handles = (#(#handles, )*);
+/// Whether an `#[actor(hooks)]` attribute says that the actor implements
+/// `Actor` itself, to define its lifecycle hooks.
+fn get_hooks(attrs: &[Attribute]) -> ::syn::Result<bool> {
+    let mut hooks = false;
+    for attr in attrs.iter().filter(|a| a.path.is_ident(ACTOR_ATTRIBUTE)) {
+        let option: Ident = attr.parse_args()?;
+        if option != HOOKS_OPTION {
+            return Err(::syn::Error::new_spanned(option, "expected `hooks`"));
+        }
+        hooks = true;
+    }
+    Ok(hooks)
+}
+
+/// Implements `ActorMeta` and the traits it relies on from the attributes of
+/// an actor, given the messages it handles besides those in `handle` and
+/// `subscribe` attributes. `Actor` is implemented with its default hooks
+/// too, unless the actor defines its own `hooks`.
+fn actor_impls(
+    generics: &Generics,
+    self_type: &::proc_macro2::TokenStream,
+    attrs: &[Attribute],
+    extra_handles: Vec<Handle>,
+    hooks: bool,
+) -> ::syn::Result<::proc_macro2::TokenStream> {
+    let publishes = get_types(PUBLISHES_ATTRIBUTE, attrs)?;
+    let mut handles = get_handles(attrs)?;
+    for handle in extra_handles {
+        add_handle(&mut handles, handle)?;
+    }
+    let routes: Vec<_> = handles.iter().map(Handle::route).collect();
+    let directs: Vec<_> = handles
+        .iter()
+        .filter(|handle| handle.direct)
+        .map(|handle| &handle.message)
+        .collect();
+    let handles: Vec<_> = handles.iter().map(|handle| &handle.message).collect();
+    let mailbox_config = get_mailbox_config(attrs)?;
+    let lag_policy = get_lag_policy(attrs)?;
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let publishes_list = message_list(&publishes);
+    // Each message is subscribed to on its own, so that a missing handler is
+    // reported for that message.
+    let subscriptions = handles.iter().map(|message| {
+        quote_spanned! {message.span()=>
+            subscriptions.extend(::yaaf::subscriptions::<Self, #message>(system)?);
         }
+    });
+    let actor = if hooks {
+        quote! {}
+    } else {
+        quote! {
+            impl #impl_generics ::yaaf::prelude::Actor for #self_type #where_clause {}
+        }
+    };
+
+    Ok(quote! {
+        #actor
+
+        impl #impl_generics ::yaaf::ActorMeta for #self_type #where_clause {
+            type Publishes = #publishes_list;
+
+            fn subscriptions(
+                system: &::yaaf::SystemHandle,
+            ) -> ::std::result::Result<::yaaf::Subscriptions<Self>, ::yaaf::error::YaafInternalError> {
+                let mut subscriptions = ::std::vec::Vec::new();
+                #( #subscriptions )*
+                ::std::result::Result::Ok(subscriptions)
+            }
+            #mailbox_config
+            #lag_policy
+        }
+        #(
+        impl #impl_generics ::yaaf::HandlerRegistered<#handles> for #self_type #where_clause {
+            #routes
+        }
+        )*
+
         #(
-        impl ::yaaf::HandlerRegistered<#handles> for #name {}
+        impl #impl_generics ::yaaf::DirectRegistered<#directs> for #self_type #where_clause {}
         )*
 
         #(
-        impl ::yaaf::Publisher<#publishes> for #name {}
+        impl #impl_generics ::yaaf::Publisher<#publishes> for #self_type #where_clause {}
         )*
     })
 }
 
+/// Implements an actor from an inherent `impl` block, in place of the `Actor`
+/// derive. The block takes the same attributes as the derive, and every
+/// method marked `#[handler]` becomes a `Handler` for the type of its last
+/// argument, so it needs no `handle` attribute. An enum listed with the
+/// `dispatch` option is instead handled by one method per variant, marked
+/// `#[handler(variant = Deposit)]`, which takes the variant's fields. Like the
+/// derive, it takes `#[actor(hooks)]` for actors that implement `Actor`
+/// themselves.
+#[proc_macro_attribute]
+pub fn actor(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = ::proc_macro2::TokenStream::from(args);
+    let hooks = if args.is_empty() {
+        false
+    } else {
+        match ::syn::parse2::<Ident>(args.clone()) {
+            Ok(option) if option == HOOKS_OPTION => true,
+            _ => {
+                return TokenStream::from(
+                    ::syn::Error::new_spanned(args, "expected `hooks`").to_compile_error(),
+                )
+            }
+        }
+    };
+    let item = parse_macro_input!(input);
+    match process_actor_attribute(item, hooks) {
+        Ok(tokens) => TokenStream::from(tokens),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
+
+fn process_actor_attribute(
+    mut item: ItemImpl,
+    hooks: bool,
+) -> ::syn::Result<::proc_macro2::TokenStream> {
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(::syn::Error::new_spanned(
+            path,
+            "`actor` goes on an inherent `impl` block",
+        ));
+    }
+
+    let (attrs, others) = item.attrs.drain(..).partition(|attr| {
+        [
+            PUBLISHES_ATTRIBUTE,
+            HANDLES_ATTRIBUTE,
+            SUBSCRIBES_ATTRIBUTE,
+            MAILBOX_ATTRIBUTE,
+            LAG_ATTRIBUTE,
+        ]
+        .iter()
+        .any(|label| attr.path.is_ident(label))
+    });
+    let attrs: Vec<Attribute> = attrs;
+    item.attrs = others;
+
+    let mut handles = vec![];
+    let mut handlers = vec![];
+    let mut variant_handlers = vec![];
+    for member in &mut item.items {
+        let method = match member {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+        let (markers, others) = method
+            .attrs
+            .drain(..)
+            .partition(|attr| attr.path.is_ident(HANDLER_ATTRIBUTE));
+        let markers: Vec<Attribute> = markers;
+        method.attrs = others;
+        let marker = match markers.as_slice() {
+            [] => continue,
+            [marker] => marker,
+            [_, extra, ..] => {
+                return Err(::syn::Error::new_spanned(
+                    extra,
+                    "a method may only be marked `handler` once",
+                ))
+            }
+        };
+
+        let mut variants = vec![];
+        let mut items = vec![];
+        if !marker.tokens.is_empty() {
+            for item in marker.parse_args_with(Punctuated::<Item, Token![,]>::parse_terminated)? {
+                match item {
+                    Item::Variant(variant) => variants.push(variant),
+                    item => items.push(item),
+                }
+            }
+        }
+        let (messages, options) = parse_handle_items(items, true)?;
+        if let Some(variant) = variants.first() {
+            if let Some(extra) = variants.get(1) {
+                return Err(::syn::Error::new_spanned(
+                    extra,
+                    "a method may only handle one variant",
+                ));
+            }
+            if let Some(extra) = messages.get(1) {
+                return Err(::syn::Error::new_spanned(
+                    extra,
+                    "a variant belongs to a single enum",
+                ));
+            }
+            if !options.is_empty() {
+                return Err(::syn::Error::new_spanned(
+                    marker,
+                    "a variant's handler takes no options, which go on its enum's `handle` attribute instead",
+                ));
+            }
+            variant_handlers.push(VariantHandler {
+                dispatch: messages.into_iter().next(),
+                variant: variant.clone(),
+                method: method.sig.ident.clone(),
+                fields: variant_fields(&method.sig)?,
+            });
+            continue;
+        }
+
+        let message = handler_message(&method.sig)?;
+        if let Some(extra) = messages.first() {
+            return Err(::syn::Error::new_spanned(
+                extra,
+                "the message type of a handler is that of its last argument",
+            ));
+        }
+        if let Some(dispatch) = &options.dispatch {
+            return Err(::syn::Error::new_spanned(
+                dispatch,
+                "a `dispatch` enum is handled by `#[handler(variant = ..)]` methods, one per variant",
+            ));
+        }
+        handles.push(options.handle(message.clone(), true));
+        handlers.push((method.sig.ident.clone(), message));
+    }
+
+    let self_type = item.self_ty.to_token_stream();
+    let dispatches = get_handles(&attrs)?
+        .into_iter()
+        .filter(|handle| handle.dispatch)
+        .map(|handle| handle.message)
+        .collect();
+    let dispatches = dispatch_handlers(&item.generics, &self_type, dispatches, variant_handlers)?;
+    let meta = actor_impls(&item.generics, &self_type, &attrs, handles, hooks)?;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    let handlers = handlers.into_iter().map(|(method, message)| {
+        quote! {
+            #[::yaaf::prelude::async_trait]
+            impl #impl_generics ::yaaf::prelude::Handler<#message> for #self_type #where_clause {
+                async fn handle(
+                    &mut self,
+                    ctx: &mut ::yaaf::prelude::Context<Self>,
+                    message: #message,
+                ) {
+                    Self::#method(self, ctx, message).await
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        #item
+        #meta
+        #(#handlers)*
+        #(#dispatches)*
+    })
+}
+
+/// Checks that a `#[handler]` method is
+/// `async fn(&mut self, ctx: &mut Context<Self>, message: M)`, returning `M`.
+fn handler_message(sig: &Signature) -> ::syn::Result<Type> {
+    const EXPECTED: &str =
+        "expected `async fn name(&mut self, ctx: &mut Context<Self>, message: Message)`";
+    if sig.asyncness.is_none() {
+        return Err(::syn::Error::new_spanned(sig, EXPECTED));
+    }
+    let mut inputs = sig.inputs.iter();
+    match (inputs.next(), inputs.next(), inputs.next(), inputs.next()) {
+        (
+            Some(FnArg::Receiver(receiver)),
+            Some(FnArg::Typed(_)),
+            Some(FnArg::Typed(message)),
+            None,
+        ) if receiver.reference.is_some() && receiver.mutability.is_some() => {
+            Ok((*message.ty).clone())
+        }
+        _ => Err(::syn::Error::new_spanned(&sig.inputs, EXPECTED)),
+    }
+}
+
+/// Checks that a `#[handler(variant = ..)]` method is
+/// `async fn(&mut self, ctx: &mut Context<Self>, fields..)`, returning how
+/// many fields it takes.
+fn variant_fields(sig: &Signature) -> ::syn::Result<usize> {
+    const EXPECTED: &str =
+        "expected `async fn name(&mut self, ctx: &mut Context<Self>, ..)`, followed by the variant's fields";
+    if sig.asyncness.is_none() {
+        return Err(::syn::Error::new_spanned(sig, EXPECTED));
+    }
+    let mut inputs = sig.inputs.iter();
+    match (inputs.next(), inputs.next()) {
+        (Some(FnArg::Receiver(receiver)), Some(FnArg::Typed(_)))
+            if receiver.reference.is_some() && receiver.mutability.is_some() =>
+        {
+            Ok(inputs.count())
+        }
+        _ => Err(::syn::Error::new_spanned(&sig.inputs, EXPECTED)),
+    }
+}
+
+/// A method marked `#[handler(variant = ..)]`, which handles one variant of a
+/// `dispatch` enum.
+struct VariantHandler {
+    /// The enum, if the attribute names it.
+    dispatch: Option<Type>,
+    variant: Ident,
+    method: Ident,
+    /// How many of the variant's fields the method takes.
+    fields: usize,
+}
+
+/// Implements `Handler` for every `dispatch` enum of an `#[actor]` impl, by
+/// passing each variant's fields to its `#[handler(variant = ..)]` method.
+/// A variant without one is reported by the compiler as a missing match arm.
+fn dispatch_handlers(
+    generics: &Generics,
+    self_type: &::proc_macro2::TokenStream,
+    dispatches: Vec<Type>,
+    variant_handlers: Vec<VariantHandler>,
+) -> ::syn::Result<Vec<::proc_macro2::TokenStream>> {
+    let keys: Vec<_> = dispatches
+        .iter()
+        .map(|dispatch| dispatch.to_token_stream().to_string())
+        .collect();
+    let mut routes: Vec<Vec<VariantHandler>> = dispatches.iter().map(|_| vec![]).collect();
+    for handler in variant_handlers {
+        let index = match &handler.dispatch {
+            Some(dispatch) => {
+                let key = dispatch.to_token_stream().to_string();
+                keys.iter().position(|other| *other == key).ok_or_else(|| {
+                    ::syn::Error::new_spanned(
+                        dispatch,
+                        "expected an enum listed in a `#[handle(.., dispatch)]` attribute of this impl",
+                    )
+                })?
+            }
+            None if dispatches.len() == 1 => 0,
+            None if dispatches.is_empty() => {
+                return Err(::syn::Error::new_spanned(
+                    &handler.variant,
+                    "the enum of a variant must be listed in a `#[handle(.., dispatch)]` attribute of this impl",
+                ))
+            }
+            None => {
+                return Err(::syn::Error::new_spanned(
+                    &handler.variant,
+                    format!(
+                        "this impl dispatches several enums, so name the one `{0}` belongs to, as in `#[handler(Command, variant = {0})]`",
+                        handler.variant
+                    ),
+                ))
+            }
+        };
+        if routes[index]
+            .iter()
+            .any(|other| other.variant == handler.variant)
+        {
+            return Err(::syn::Error::new_spanned(
+                &handler.variant,
+                format!("`{}` already has a handler", handler.variant),
+            ));
+        }
+        routes[index].push(handler);
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(dispatches
+        .iter()
+        .zip(routes)
+        .map(|(dispatch, handlers)| {
+            // A type alias in the method cannot use the impl's parameters, so
+            // it declares the ones the enum mentions itself, to be inferred.
+            let params: Vec<_> = generics
+                .type_params()
+                .map(|param| &param.ident)
+                .filter(|param| mentions(dispatch.to_token_stream(), param))
+                .collect();
+            let (alias_generics, inferred) = if params.is_empty() {
+                (quote! {}, quote! {})
+            } else {
+                let holes = params.iter().map(|_| quote! { _ });
+                (quote! { <#(#params),*> }, quote! { ::<#(#holes),*> })
+            };
+            let arms = handlers.iter().map(|handler| {
+                let VariantHandler {
+                    variant,
+                    method,
+                    fields,
+                    ..
+                } = handler;
+                let fields: Vec<_> = (0..*fields)
+                    .map(|index| Ident::new(&format!("field{}", index), method.span()))
+                    .collect();
+                quote_spanned! {method.span()=>
+                    YaafVariants #inferred::#variant(#(#fields),*) => {
+                        Self::#method(self, ctx, #(#fields),*).await
+                    }
+                }
+            });
+            let variants = quote_spanned! {dispatch.span()=>
+                ::yaaf::prelude::Dispatch::variants(message)
+            };
+            quote! {
+                #[::yaaf::prelude::async_trait]
+                impl #impl_generics ::yaaf::prelude::Handler<#dispatch> for #self_type #where_clause {
+                    async fn handle(
+                        &mut self,
+                        ctx: &mut ::yaaf::prelude::Context<Self>,
+                        message: #dispatch,
+                    ) {
+                        type YaafVariants #alias_generics = <#dispatch as ::yaaf::prelude::Dispatch>::Variants;
+                        match #variants {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+        })
+        .collect())
+}
+
+/// Whether `tokens` contain `ident` at any depth.
+fn mentions(tokens: ::proc_macro2::TokenStream, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        ::proc_macro2::TokenTree::Ident(other) => other == *ident,
+        ::proc_macro2::TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// A message type named by a `handle` or `subscribe` attribute, with the
+/// options those attributes give it.
+struct Handle {
+    message: Type,
+    /// Whether it may be told directly, i.e. is named by a `handle` attribute.
+    direct: bool,
+    /// Whether it is received when published, i.e. is named by a `subscribe`
+    /// attribute or by a `handle` attribute without `direct_only`.
+    broadcast: bool,
+    /// Whether it is an enum of messages to pass on to their handlers, i.e. is
+    /// named by an attribute with the `dispatch` option.
+    dispatch: bool,
+    topics: Vec<LitStr>,
+    filter: Option<Path>,
+}
+
+impl Handle {
+    /// Builds the `route` override, unless the defaults are kept.
+    fn route(&self) -> ::proc_macro2::TokenStream {
+        if self.broadcast && self.topics.is_empty() && self.filter.is_none() {
+            return quote! {};
+        }
+        let message = &self.message;
+        let subscribe = self.broadcast;
+        let topics = &self.topics;
+        let filter = match &self.filter {
+            Some(filter) => quote! { Some(#filter as fn(&#message) -> bool) },
+            None => quote! { None },
+        };
+        quote! {
+            fn route() -> ::yaaf::Route<#message> {
+                ::yaaf::Route {
+                    subscribe: #subscribe,
+                    topics: &[#(#topics),*],
+                    filter: #filter,
+                }
+            }
+        }
+    }
+}
+
+/// The options of a `handle`, `subscribe` or `handler` attribute, which
+/// apply to every type it names.
+#[derive(Default)]
+struct HandleOptions {
+    direct_only: Option<Type>,
+    dispatch: Option<Type>,
+    topics: Vec<LitStr>,
+    filter: Option<Path>,
+}
+
+impl HandleOptions {
+    fn is_empty(&self) -> bool {
+        self.direct_only.is_none()
+            && self.dispatch.is_none()
+            && self.topics.is_empty()
+            && self.filter.is_none()
+    }
+
+    fn handle(&self, message: Type, direct: bool) -> Handle {
+        Handle {
+            message,
+            direct,
+            broadcast: self.direct_only.is_none(),
+            dispatch: self.dispatch.is_some(),
+            topics: self.topics.clone(),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+/// Parses `#[handle(Order, Refund, topic = "eu", filter = "is_large")]`,
+/// `#[handle(Order, direct_only)]`, `#[handle(Command, dispatch)]` and
+/// `#[subscribe(Order, topic = "eu")]` attributes, where the options apply to
+/// every type in the same attribute.
+fn get_handles(attrs: &[Attribute]) -> ::syn::Result<Vec<Handle>> {
+    let mut result: Vec<Handle> = vec![];
+    for attr in attrs
+        .iter()
+        .filter(|a| a.path.is_ident(HANDLES_ATTRIBUTE) || a.path.is_ident(SUBSCRIBES_ATTRIBUTE))
+    {
+        let direct = attr.path.is_ident(HANDLES_ATTRIBUTE);
+        let list = attr.parse_args_with(Punctuated::<Item, Token![,]>::parse_terminated)?;
+        let (messages, options) = parse_handle_items(list, direct)?;
+        for message in messages {
+            add_handle(&mut result, options.handle(message, direct))?;
+        }
+    }
+    Ok(result)
+}
+
+/// Splits the entries of an attribute into message types and options, where
+/// `direct_only` is only allowed on `direct` ones.
+fn parse_handle_items(
+    list: impl IntoIterator<Item = Item>,
+    direct: bool,
+) -> ::syn::Result<(Vec<Type>, HandleOptions)> {
+    let mut messages = vec![];
+    let mut options = HandleOptions::default();
+    for item in list {
+        match item {
+            Item::Message(message) if direct && is_ident(&message, "direct_only") => {
+                options.direct_only = Some(message)
+            }
+            Item::Message(message) if is_ident(&message, "dispatch") => {
+                options.dispatch = Some(message)
+            }
+            Item::Message(message) => messages.push(message),
+            Item::Option(pair) => {
+                match (
+                    pair.path.get_ident().map(Ident::to_string).as_deref(),
+                    &pair.lit,
+                ) {
+                    (Some("topic"), Lit::Str(value)) => options.topics.push(value.clone()),
+                    (Some("filter"), Lit::Str(value)) => {
+                        options.filter = Some(value.parse::<Path>()?)
+                    }
+                    _ => {
+                        return Err(::syn::Error::new_spanned(
+                            pair,
+                            "expected `topic = \"<topic>\"` or `filter = \"<function>\"`",
+                        ))
+                    }
+                }
+            }
+            Item::Variant(variant) => {
+                return Err(::syn::Error::new_spanned(
+                    variant,
+                    "`variant` only goes on a `#[handler]` method",
+                ))
+            }
+        }
+    }
+    if let Some(direct_only) = &options.direct_only {
+        if !options.topics.is_empty() || options.filter.is_some() {
+            return Err(::syn::Error::new_spanned(
+                direct_only,
+                "`direct_only` messages are not published to the actor, so cannot have a topic or filter",
+            ));
+        }
+    }
+    Ok((messages, options))
+}
+
+/// Adds `handle` to `handles`, merging it with any for the same type, which
+/// then gets the topics of both and is received both ways if either allows it.
+fn add_handle(handles: &mut Vec<Handle>, handle: Handle) -> ::syn::Result<()> {
+    let key = handle.message.to_token_stream().to_string();
+    let existing = match handles
+        .iter_mut()
+        .find(|existing| existing.message.to_token_stream().to_string() == key)
+    {
+        Some(existing) => existing,
+        None => {
+            handles.push(handle);
+            return Ok(());
+        }
+    };
+    if handle.filter.is_some() && existing.filter.is_some() {
+        return Err(::syn::Error::new_spanned(
+            handle.message,
+            "only one filter may be given per message type",
+        ));
+    }
+    existing.direct |= handle.direct;
+    existing.broadcast |= handle.broadcast;
+    existing.dispatch |= handle.dispatch;
+    existing.topics.extend(handle.topics);
+    existing.filter = existing.filter.take().or(handle.filter);
+    Ok(())
+}
+
+#[proc_macro_derive(Dispatch)]
+pub fn dispatch_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input);
+    match process_dispatch_derive(ast) {
+        Ok(tokens) => TokenStream::from(tokens),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
+
+/// Implements `Dispatch` for an enum, with a copy of it whose variants hold
+/// their fields in order, so that the `actor` attribute can pass them to a
+/// method per variant without knowing whether they are named.
+fn process_dispatch_derive(input: DeriveInput) -> ::syn::Result<::proc_macro2::TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(::syn::Error::new_spanned(
+                &input.ident,
+                "`Dispatch` can only be derived for enums",
+            ))
+        }
+    };
+    let mut variants = vec![];
+    let mut types = vec![];
+    let mut patterns = vec![];
+    let mut bindings = vec![];
+    for variant in &data.variants {
+        let fields: Vec<_> = variant.fields.iter().collect();
+        let names: Vec<_> = (0..fields.len())
+            .map(|index| Ident::new(&format!("field{}", index), variant.span()))
+            .collect();
+        let members = fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| match &field.ident {
+                Some(name) => quote! { #name },
+                None => {
+                    let index = ::syn::Index::from(index);
+                    quote! { #index }
+                }
+            });
+        // Braces match named, tuple and unit variants alike.
+        patterns.push(quote! { { #(#members: #names),* } });
+        variants.push(&variant.ident);
+        types.push(fields.iter().map(|field| &field.ty).collect::<Vec<_>>());
+        bindings.push(names);
+    }
+
+    let name = &input.ident;
+    let variants_type = Ident::new("YaafVariants", ::proc_macro2::Span::call_site());
+    let generics = &input.generics;
+    let (_, type_generics, where_clause) = generics.split_for_impl();
+    // Generic enums are only messages for some parameters.
+    let mut dispatch_generics = generics.clone();
+    dispatch_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { #name #type_generics: ::yaaf::Message });
+    let (impl_generics, _, dispatch_where_clause) = dispatch_generics.split_for_impl();
+
+    Ok(quote! {
+        const _: () = {
+            pub enum #variants_type #generics #where_clause {
+                #( #variants(#(#types),*), )*
+            }
+
+            impl #impl_generics ::yaaf::prelude::Dispatch for #name #type_generics #dispatch_where_clause {
+                type Variants = #variants_type #type_generics;
+
+                fn variants(self) -> Self::Variants {
+                    match self {
+                        #(
+                        Self::#variants #patterns => #variants_type::#variants(#(#bindings),*),
+                        )*
+                    }
+                }
+            }
+        };
+    })
+}
+
 #[proc_macro_derive(Source, attributes(publish))]
 pub fn source_derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input);
@@ -41,43 +733,224 @@ pub fn source_derive(input: TokenStream) -> TokenStream {
 }
 
 fn process_source_derive(input: DeriveInput) -> TokenStream {
-    let publishes = get_idents(PUBLISHES_ATTRIBUTE, &input);
+    let publishes = match get_types(PUBLISHES_ATTRIBUTE, &input.attrs) {
+        Ok(publishes) => publishes,
+        Err(error) => return TokenStream::from(error.to_compile_error()),
+    };
 
     let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let publishes_list = message_list(&publishes);
     TokenStream::from(quote! {
-        impl ::yaaf::SourceMeta for #name {
-            type Publishes = (#(#publishes, )*);
+        impl #impl_generics ::yaaf::SourceMeta for #name #type_generics #where_clause {
+            type Publishes = #publishes_list;
         }
 
         #(
-        impl ::yaaf::Publisher<#publishes> for #name {}
+        impl #impl_generics ::yaaf::Publisher<#publishes> for #name #type_generics #where_clause {}
         )*
     })
 }
 
-fn get_idents(label: &str, input: &DeriveInput) -> Vec<Ident> {
-    let mut result = vec![];
-    for att in input.attrs.iter().filter(|a| a.path.is_ident(label)) {
-        result.extend(parse_attr(&att));
+/// Builds the type-level list of `messages`, nested so that it has no limit
+/// on its length.
+fn message_list<T: ToTokens>(messages: &[T]) -> ::proc_macro2::TokenStream {
+    messages.iter().rev().fold(quote! { () }, |tail, message| {
+        quote! { ::yaaf::Cons<#message, #tail> }
+    })
+}
+
+/// An entry in a `publish`, `handle` or `subscribe` attribute.
+enum Item {
+    /// A message type, such as `Order` or `events::Update<K, V>`.
+    Message(Type),
+    /// An option such as `topic = "eu"`.
+    Option(MetaNameValue),
+    /// The variant a `handler` method handles, as in `variant = Deposit`.
+    Variant(Ident),
+}
+
+impl Parse for Item {
+    fn parse(input: ParseStream) -> ::syn::Result<Self> {
+        if input.peek(Ident) && input.peek2(Token![=]) {
+            if input.fork().parse::<Ident>()? == VARIANT_OPTION {
+                input.parse::<Ident>()?;
+                input.parse::<Token![=]>()?;
+                return Ok(Item::Variant(input.parse()?));
+            }
+            Ok(Item::Option(input.parse()?))
+        } else {
+            Ok(Item::Message(input.parse()?))
+        }
     }
-    result
 }
 
-fn parse_attr(attr: &Attribute) -> Vec<Ident> {
-    let list = attr
-        .parse_args_with(Punctuated::<NestedMeta, Token![,]>::parse_terminated)
-        .expect("failed to parse attribute");
+/// Whether `ty` is the bare identifier `name`.
+fn is_ident(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident(name),
+        _ => false,
+    }
+}
 
+/// Parses the message types of every `label` attribute, which take no
+/// options.
+fn get_types(label: &str, attrs: &[Attribute]) -> ::syn::Result<Vec<Type>> {
     let mut result = vec![];
-    for item in list {
-        match item {
-            ::syn::NestedMeta::Meta(m) => {
-                result.push(m.path().get_ident().unwrap().clone());
+    for attr in attrs.iter().filter(|a| a.path.is_ident(label)) {
+        let list = attr.parse_args_with(Punctuated::<Item, Token![,]>::parse_terminated)?;
+        for item in list {
+            match item {
+                Item::Message(message) => result.push(message),
+                Item::Option(pair) => {
+                    return Err(::syn::Error::new_spanned(pair, "expected a message type"))
+                }
+                Item::Variant(variant) => {
+                    return Err(::syn::Error::new_spanned(
+                        variant,
+                        "expected a message type",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Builds the `mailbox_config` override from a
+/// `#[mailbox(capacity = 128, overflow = "drop_oldest")]` attribute.
+fn get_mailbox_config(attrs: &[Attribute]) -> ::syn::Result<::proc_macro2::TokenStream> {
+    let pairs = match get_name_values(MAILBOX_ATTRIBUTE, attrs)? {
+        Some(pairs) => pairs,
+        None => return Ok(quote! {}),
+    };
+
+    let mut capacity = None;
+    let mut overflow = None;
+    for pair in pairs {
+        match (
+            pair.path.get_ident().map(Ident::to_string).as_deref(),
+            &pair.lit,
+        ) {
+            (Some("capacity"), Lit::Int(value)) => {
+                let value_capacity = value.base10_parse::<usize>()?;
+                if value_capacity == 0 {
+                    return Err(::syn::Error::new_spanned(
+                        value,
+                        "mailbox capacity must be positive",
+                    ));
+                }
+                capacity = Some(value_capacity);
             }
-            ::syn::NestedMeta::Lit(_) => {
-                panic!("Identifier required");
+            (Some("overflow"), Lit::Str(value)) => {
+                let policy = match value.value().as_str() {
+                    "block" => quote! { ::yaaf::OverflowPolicy::Block },
+                    "fail" => quote! { ::yaaf::OverflowPolicy::Fail },
+                    "drop_oldest" => quote! { ::yaaf::OverflowPolicy::DropOldest },
+                    "drop_newest" => quote! { ::yaaf::OverflowPolicy::DropNewest },
+                    _ => return Err(::syn::Error::new_spanned(
+                        value,
+                        "expected one of \"block\", \"fail\", \"drop_oldest\" or \"drop_newest\"",
+                    )),
+                };
+                overflow = Some((pair.clone(), policy));
             }
+            _ => {
+                return Err(::syn::Error::new_spanned(
+                    pair,
+                    "expected `capacity = <integer>` or `overflow = \"<policy>\"`",
+                ))
+            }
+        }
+    }
+
+    let config =
+        match (capacity, overflow) {
+            (Some(capacity), Some((_, overflow))) => {
+                quote! { ::yaaf::MailboxConfig::bounded(#capacity, #overflow) }
+            }
+            (Some(capacity), None) => {
+                quote! { ::yaaf::MailboxConfig::bounded(#capacity, ::yaaf::OverflowPolicy::Block) }
+            }
+            (None, Some((pair, _))) => return Err(::syn::Error::new_spanned(
+                pair,
+                "an overflow policy needs a `capacity`, since an unbounded mailbox never overflows",
+            )),
+            (None, None) => quote! { ::yaaf::MailboxConfig::unbounded() },
+        };
+    Ok(quote! {
+        fn mailbox_config() -> ::yaaf::MailboxConfig {
+            #config
+        }
+    })
+}
+
+/// Builds the `lag_policy` override from a `#[lag(policy = "notify")]`
+/// attribute.
+fn get_lag_policy(attrs: &[Attribute]) -> ::syn::Result<::proc_macro2::TokenStream> {
+    let pairs = match get_name_values(LAG_ATTRIBUTE, attrs)? {
+        Some(pairs) => pairs,
+        None => return Ok(quote! {}),
+    };
+
+    let mut policy = quote! { ::yaaf::LagPolicy::Count };
+    for pair in pairs {
+        match (
+            pair.path.get_ident().map(Ident::to_string).as_deref(),
+            &pair.lit,
+        ) {
+            (Some("policy"), Lit::Str(value)) => {
+                policy = match value.value().as_str() {
+                    "count" => quote! { ::yaaf::LagPolicy::Count },
+                    "notify" => quote! { ::yaaf::LagPolicy::Notify },
+                    "fail" => quote! { ::yaaf::LagPolicy::Fail },
+                    _ => {
+                        return Err(::syn::Error::new_spanned(
+                            value,
+                            "expected one of \"count\", \"notify\" or \"fail\"",
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(::syn::Error::new_spanned(
+                    pair,
+                    "expected `policy = \"<policy>\"`",
+                ))
+            }
+        }
+    }
+
+    Ok(quote! {
+        fn lag_policy() -> ::yaaf::LagPolicy {
+            #policy
         }
+    })
+}
+
+/// Parses an attribute made of `name = value` pairs, if it is present.
+fn get_name_values(label: &str, attrs: &[Attribute]) -> ::syn::Result<Option<Vec<MetaNameValue>>> {
+    let mut found = attrs.iter().filter(|a| a.path.is_ident(label));
+    let attr = match found.next() {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    if let Some(duplicate) = found.next() {
+        return Err(::syn::Error::new_spanned(
+            duplicate,
+            format!("duplicate `{}` attribute", label),
+        ));
     }
-    result
+
+    let list = attr.parse_args_with(Punctuated::<NestedMeta, Token![,]>::parse_terminated)?;
+    list.into_iter()
+        .map(|item| match item {
+            NestedMeta::Meta(Meta::NameValue(pair)) => Ok(pair),
+            other => Err(::syn::Error::new_spanned(
+                other,
+                "expected `name = value` pairs",
+            )),
+        })
+        .collect::<::syn::Result<_>>()
+        .map(Some)
 }
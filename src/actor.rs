@@ -1,64 +1,412 @@
 use crate::{
-    channel::DirectChannel,
-    error::AddressError,
-    handler::{detail::HandlesList, Handler},
+    context::{Context, StopMode},
+    envelope::StopEnvelope,
+    error::{AddressError, YaafInternalError},
+    handler::{
+        detail::{Subscriptions, Tellable},
+        DirectRegistered, Handler,
+    },
+    lag::{LagPolicy, Lagged},
     message::{detail::MessageList, Message},
+    metadata::{Headers, Metadata, Origin},
+    metrics::{ActorMetrics, MetricsRecorder},
+    queue::{MailboxConfig, MailboxSender, QueueError},
+    responder::{detail::Askable, Responder},
+    stream::StreamFinished,
+    system::SystemHandle,
 };
-use ::std::{any::TypeId, collections::HashMap, marker::PhantomData, sync::Arc};
-use ::tokio::sync::mpsc::UnboundedSender;
+use ::async_trait::async_trait;
+use ::std::{
+    any::type_name,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use ::tokio::sync::{oneshot, watch};
 
-pub trait Actor: Sized + HandlesList<<Self as Actor>::Handles> {
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not an actor",
+    note = "derive `Actor` for `{Self}`, or add `#[actor]` to an `impl` block of it"
+)]
+pub trait ActorMeta: Sized {
     type Publishes: MessageList;
-    type Handles: MessageList;
+
+    /// Subscribes to the messages in the `handle` and `subscribe` attributes
+    /// of the [`Actor`] derive.
+    ///
+    /// [`Actor`]: ::yaaf_macros::Actor
+    fn subscriptions(system: &SystemHandle) -> Result<Subscriptions<Self>, YaafInternalError>;
+
+    /// Set by the `mailbox` attribute of the [`Actor`] derive.
+    ///
+    /// [`Actor`]: ::yaaf_macros::Actor
+    fn mailbox_config() -> MailboxConfig {
+        MailboxConfig::default()
+    }
+
+    /// Set by the `lag` attribute of the [`Actor`] derive.
+    ///
+    /// [`Actor`]: ::yaaf_macros::Actor
+    fn lag_policy() -> LagPolicy {
+        LagPolicy::default()
+    }
+}
+
+/// Lifecycle hooks for an actor.
+///
+/// Each hook is called exactly once per actor, regardless of how many message
+/// types it handles.
+///
+/// The [`Actor`](macro@crate::Actor) derive and the [`actor`](macro@crate::actor)
+/// attribute implement this with the default hooks. An actor that defines its
+/// own is marked `#[actor(hooks)]` instead, and implements it itself:
+///
+/// ```rust
+/// # use ::yaaf::prelude::*;
+/// #[derive(Clone, Debug)]
+/// struct Ping;
+///
+/// #[derive(Actor)]
+/// #[actor(hooks)]
+/// #[handle(Ping)]
+/// struct Paddle;
+///
+/// #[async_trait]
+/// impl Actor for Paddle {
+///     async fn started(&mut self, _ctx: &mut Context<Self>) {
+///         println!("Ready");
+///     }
+/// }
+///
+/// #[async_trait]
+/// impl Handler<Ping> for Paddle {
+///     async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Ping) {}
+/// }
+/// ```
+#[async_trait]
+pub trait Actor: ActorMeta + Send {
+    /// Called once the actor is running, before any messages are handled.
+    async fn started(&mut self, _ctx: &mut Context<Self>) {}
+
+    /// Called when the actor has been asked to stop.
+    async fn stopping(&mut self, _ctx: &mut Context<Self>) {}
+
+    /// Called after the actor has stopped handling messages.
+    async fn stopped(&mut self) {}
+
+    /// Called when a child spawned with [`Context::spawn_child`] terminates.
+    ///
+    /// The children of an instance that panicked are stopped before it is
+    /// restarted, and its replacement is not told about them.
+    async fn child_terminated(&mut self, _ctx: &mut Context<Self>, _terminated: ChildTerminated) {}
+
+    /// Called when an actor watched with [`Context::watch`] terminates.
+    async fn terminated(&mut self, _ctx: &mut Context<Self>, _terminated: Terminated) {}
+
+    /// Called when a stream added with [`Context::add_stream`] ends.
+    async fn stream_finished(&mut self, _ctx: &mut Context<Self>, _finished: StreamFinished) {}
+
+    /// Called when the actor misses published messages and its lag policy is
+    /// [`LagPolicy::Notify`].
+    async fn lagged(&mut self, _ctx: &mut Context<Self>, _lagged: Lagged) {}
+}
+
+/// Uniquely identifies an actor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ActorId(u64);
+
+impl ActorId {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        ActorId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Why an actor terminated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminationReason {
+    /// The actor was stopped normally, e.g. through [`ActorAddress::stop`] or
+    /// by its parent.
+    Stopped,
+    /// The actor was stopped because its system shut down.
+    Shutdown,
+    /// The actor panicked and was not supervised.
+    Panicked,
+    /// The actor fell behind a broadcast channel with [`LagPolicy::Fail`]
+    /// and was not supervised.
+    Lagged,
+    /// The actor failed, and its supervision strategy gave up restarting it.
+    SupervisorGaveUp,
+    /// The actor was still running when a graceful shutdown timed out.
+    Aborted,
 }
 
-#[derive(Debug)]
-pub struct ActorAddress<A: Actor> {
-    channels: HashMap<TypeId, Box<dyn DirectChannel>>,
-    _actor: PhantomData<Arc<A>>,
+/// Delivered to [`Actor::terminated`] when a watched actor terminates.
+///
+/// [`Actor::terminated`]: crate::prelude::Actor::terminated
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Terminated {
+    pub id: ActorId,
+    pub reason: TerminationReason,
+}
+
+/// Delivered to [`Actor::child_terminated`] when a child actor terminates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChildTerminated {
+    pub id: ActorId,
+    pub reason: TerminationReason,
+}
+
+/// A handle for sending messages to an actor.
+///
+/// Addresses compare, hash and print by the actor's [`ActorId`], so that all
+/// clones of an address are equal to each other and to no other actor's.
+pub struct ActorAddress<A> {
+    id: ActorId,
+    name: Option<Arc<str>>,
+    metrics: Arc<MetricsRecorder>,
+    sender: MailboxSender<A>,
+    termination: watch::Receiver<Option<TerminationReason>>,
+    _actor: PhantomData<fn() -> A>,
 }
 
 impl<A: Actor> Clone for ActorAddress<A> {
     fn clone(&self) -> Self {
         ActorAddress {
-            channels: self.channels.clone(),
+            id: self.id,
+            name: self.name.clone(),
+            metrics: self.metrics.clone(),
+            sender: self.sender.clone(),
+            termination: self.termination.clone(),
             _actor: PhantomData,
         }
     }
 }
 
+impl<A: Actor> PartialEq for ActorAddress<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<A: Actor> Eq for ActorAddress<A> {}
+
+impl<A: Actor> Hash for ActorAddress<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<A: Actor> fmt::Debug for ActorAddress<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ActorAddress");
+        debug.field("id", &self.id);
+        if let Some(name) = &self.name {
+            debug.field("name", name);
+        }
+        debug.field("actor", &type_name::<A>()).finish()
+    }
+}
+
 impl<A: Actor> ActorAddress<A> {
-    pub(crate) fn new(channels: HashMap<TypeId, Box<dyn DirectChannel>>) -> Self {
+    pub(crate) fn new(
+        id: ActorId,
+        name: Option<Arc<str>>,
+        sender: MailboxSender<A>,
+        metrics: Arc<MetricsRecorder>,
+        termination: watch::Receiver<Option<TerminationReason>>,
+    ) -> Self {
         ActorAddress {
-            channels,
+            id,
+            name,
+            metrics,
+            sender,
+            termination,
             _actor: PhantomData,
         }
     }
+
+    pub fn id(&self) -> ActorId {
+        self.id
+    }
+
+    /// The name the actor was added with, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Waits for the actor to terminate, returning why it did.
+    pub async fn closed(&self) -> TerminationReason {
+        closed(self.termination.clone()).await
+    }
+
+    /// Returns the current counters for the actor.
+    pub fn metrics(&self) -> ActorMetrics {
+        self.metrics.snapshot()
+    }
+
+    /// Stops the actor once it has handled the messages already queued,
+    /// running its stop hooks.
+    ///
+    /// The actor accepts no more messages, so later tells and asks fail with
+    /// [`AddressError::ActorStopped`], as does stopping it again.
+    pub fn stop(&self) -> Result<(), AddressError>
+    where
+        A: 'static,
+    {
+        self.sender
+            .close_with(Box::new(StopEnvelope::new(
+                StopMode::Immediate,
+                TerminationReason::Stopped,
+            )))
+            .map_err(|_| AddressError::ActorStopped)
+    }
+
+    /// Queues a message without waiting, see [`Tell::tell`].
+    ///
+    /// This and [`ask`](ActorAddress::ask) are also inherent so that a message
+    /// the actor does not handle is reported through the diagnostics of the
+    /// missing trait, rather than as a mismatched [`Tell`] or [`Ask`].
+    pub fn tell<M>(&self, message: M) -> Result<(), AddressError>
+    where
+        A: 'static,
+        M: Message + Tellable<A>,
+    {
+        self.tell_with(message, Headers::new())
+    }
+
+    /// Queues a message without waiting, like [`tell`](ActorAddress::tell),
+    /// with `headers` on top of those it inherits from the message being
+    /// handled, if any.
+    pub fn tell_with<M>(&self, message: M, headers: Headers) -> Result<(), AddressError>
+    where
+        A: 'static,
+        M: Message + Tellable<A>,
+    {
+        let metadata = Metadata::new(Origin::Direct, headers);
+        self.sender
+            .try_send(message.envelope(metadata))
+            .map_err(queue_error)
+    }
+
+    /// Queues a message, waiting for room if the actor's bounded mailbox is
+    /// full, see [`Tell::tell_wait`].
+    pub async fn tell_wait<M>(&self, message: M) -> Result<(), AddressError>
+    where
+        A: 'static,
+        M: Message + Tellable<A>,
+    {
+        let metadata = Metadata::new(Origin::Direct, Headers::new());
+        self.sender
+            .send(message.envelope(metadata))
+            .await
+            .map_err(queue_error)
+    }
+
+    /// Sends a request and waits for the response, see [`Ask::ask`].
+    pub async fn ask<M>(&self, message: M) -> Result<<M as Askable<A>>::Response, AddressError>
+    where
+        A: 'static,
+        M: Message + Askable<A>,
+    {
+        let metadata = Metadata::new(Origin::Direct, Headers::new());
+        let (reply, response) = oneshot::channel();
+        self.sender
+            .send(message.envelope(metadata, reply))
+            .await
+            .map_err(queue_error)?;
+
+        response.await.map_err(|_| AddressError::NoResponse)
+    }
+
+    /// Asks the actor to stop once it has handled the messages already queued.
+    pub(crate) fn request_stop(&self, mode: StopMode, reason: TerminationReason)
+    where
+        A: 'static,
+    {
+        // The actor may already have stopped, which is fine.
+        let _ = self.sender.force(Box::new(StopEnvelope::new(mode, reason)));
+    }
+
+    pub(crate) fn sender(&self) -> &MailboxSender<A> {
+        &self.sender
+    }
+
+    pub(crate) fn termination(&self) -> watch::Receiver<Option<TerminationReason>> {
+        self.termination.clone()
+    }
 }
 
+#[async_trait]
 pub trait Tell<M: Message> {
+    /// Queues a message without waiting.
+    ///
+    /// Fails with [`AddressError::MailboxFull`] if the actor's bounded
+    /// mailbox is full and its overflow policy is `Block` or `Fail`, and with
+    /// [`AddressError::ActorStopped`] once the actor has stopped.
     fn tell(&self, message: M) -> Result<(), AddressError>;
+
+    /// Queues a message, waiting for room if the actor's bounded mailbox is
+    /// full and its overflow policy is `Block`.
+    async fn tell_wait(&self, message: M) -> Result<(), AddressError>;
 }
 
+#[async_trait]
 impl<H, M> Tell<M> for ActorAddress<H>
 where
-    H: Handler<M>,
+    H: 'static + Handler<M> + DirectRegistered<M>,
     M: Message,
 {
     fn tell(&self, message: M) -> Result<(), AddressError> {
-        let channel = self
-            .channels
-            .get(&TypeId::of::<M>())
-            .ok_or(AddressError::ChannelLookupError)?
-            .as_any()
-            .downcast_ref::<UnboundedSender<M>>()
-            .ok_or(AddressError::ChannelLookupError)?;
-
-        channel
-            .send(message)
-            .map_err(|source| AddressError::TellFailure {
-                source: source.into(),
-            })?;
-        Ok(())
+        ActorAddress::tell(self, message)
+    }
+
+    async fn tell_wait(&self, message: M) -> Result<(), AddressError> {
+        ActorAddress::tell_wait(self, message).await
+    }
+}
+
+fn queue_error(error: QueueError) -> AddressError {
+    match error {
+        QueueError::Closed => AddressError::ActorStopped,
+        QueueError::Full => AddressError::MailboxFull,
     }
 }
+
+#[async_trait]
+pub trait Ask<M: Message> {
+    type Response;
+
+    async fn ask(&self, message: M) -> Result<Self::Response, AddressError>;
+}
+
+#[async_trait]
+impl<R, M> Ask<M> for ActorAddress<R>
+where
+    R: 'static + Responder<M>,
+    M: Message,
+{
+    type Response = R::Response;
+
+    async fn ask(&self, message: M) -> Result<Self::Response, AddressError> {
+        ActorAddress::ask(self, message).await
+    }
+}
+
+/// Waits for an actor to publish why it terminated.
+pub(crate) async fn closed(
+    mut termination: watch::Receiver<Option<TerminationReason>>,
+) -> TerminationReason {
+    let reason = match termination.wait_for(Option::is_some).await {
+        Ok(reason) => *reason,
+        // The actor's task was dropped before it could say why, which only
+        // happens when the runtime itself shuts down.
+        Err(_) => None,
+    };
+    reason.unwrap_or(TerminationReason::Aborted)
+}
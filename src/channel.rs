@@ -1,28 +1,212 @@
-use crate::message::Message;
+use crate::{
+    envelope::{Envelope, TellEnvelope},
+    handler::Handler,
+    lag::Lagged,
+    message::Message,
+    metadata::{Headers, Metadata, Origin},
+};
+use ::async_trait::async_trait;
 use ::dyn_clone::{clone_trait_object, DynClone};
-use ::std::{any::Any, fmt::Debug};
-use ::tokio::sync::{broadcast, mpsc};
+use ::futures::stream::{unfold, Stream};
+use ::std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+};
+use ::tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+};
 
-pub trait DirectChannel: Any + DynClone + Debug + Send + Sync {
+pub trait BroadcastChannel: Any + DynClone + Debug + Send + Sync {
     fn as_any(&self) -> &dyn Any;
+
+    /// The number of subscribers currently receiving from the channel.
+    fn receiver_count(&self) -> usize;
 }
 
-clone_trait_object!(DirectChannel);
+clone_trait_object!(BroadcastChannel);
 
-impl<M: 'static + Message> DirectChannel for mpsc::UnboundedSender<M> {
+impl<M: 'static + Message> BroadcastChannel for Sender<M> {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn receiver_count(&self) -> usize {
+        broadcast::Sender::receiver_count(self)
+    }
 }
 
-pub trait BroadcastChannel: Any + DynClone + Debug + Send + Sync {
-    fn as_any(&self) -> &dyn Any;
+/// A published message, with the metadata every subscriber receives it with.
+#[derive(Clone, Debug)]
+pub(crate) struct Published<M> {
+    message: M,
+    metadata: Metadata,
 }
 
-clone_trait_object!(BroadcastChannel);
+impl<M> Published<M> {
+    /// Publishes `message` now, from the actor whose task this runs in, if
+    /// any.
+    pub(crate) fn new(message: M, headers: Headers) -> Self {
+        Published {
+            message,
+            metadata: Metadata::new(Origin::Broadcast, headers),
+        }
+    }
+}
 
-impl<M: 'static + Message> BroadcastChannel for broadcast::Sender<M> {
-    fn as_any(&self) -> &dyn Any {
-        self
+pub(crate) type Sender<M> = broadcast::Sender<Published<M>>;
+
+pub(crate) type Receiver<M> = broadcast::Receiver<Published<M>>;
+
+/// Creates a broadcast channel for `M`s that buffers up to `capacity`
+/// messages.
+pub(crate) fn channel<M: Message>(capacity: usize) -> Sender<M> {
+    broadcast::channel(capacity).0
+}
+
+/// The capacities of the broadcast channels created by a system.
+#[derive(Clone, Debug)]
+pub struct ChannelCapacities {
+    default: usize,
+    per_type: HashMap<TypeId, usize>,
+}
+
+impl ChannelCapacities {
+    pub(crate) fn new(default: usize) -> Self {
+        ChannelCapacities {
+            default,
+            per_type: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn set_default(&mut self, capacity: usize) {
+        self.default = capacity;
+    }
+
+    pub(crate) fn set<M: Message>(&mut self, capacity: usize) {
+        self.per_type.insert(TypeId::of::<M>(), capacity);
+    }
+
+    pub(crate) fn of<M: Message>(&self) -> usize {
+        self.per_type
+            .get(&TypeId::of::<M>())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// What a subscription produced.
+pub enum Received<A> {
+    Message(Box<dyn Envelope<A>>),
+    /// The subscriber fell behind and missed messages.
+    Lagged(Lagged),
+}
+
+/// A type-erased subscription to one broadcast channel.
+#[async_trait]
+pub trait Subscription<A>: Send {
+    /// Returns `None` once the channel has closed.
+    async fn recv(&mut self) -> Option<Received<A>>;
+
+    /// Returns `None` if no message is ready.
+    fn try_recv(&mut self) -> Option<Received<A>>;
+
+    /// Discards the messages waiting to be received, returning how many of
+    /// them the actor would have handled.
+    fn discard_pending(&mut self) -> usize;
+}
+
+/// A subscription that skips the messages its filter rejects, so that they
+/// never reach the actor's mailbox.
+pub(crate) struct Filtered<M> {
+    receiver: Receiver<M>,
+    filter: Option<fn(&M) -> bool>,
+}
+
+impl<M> Filtered<M> {
+    pub(crate) fn new(receiver: Receiver<M>, filter: Option<fn(&M) -> bool>) -> Self {
+        Filtered { receiver, filter }
+    }
+
+    fn accepts(&self, message: &M) -> bool {
+        self.filter.is_none_or(|filter| filter(message))
+    }
+}
+
+#[async_trait]
+impl<A, M> Subscription<A> for Filtered<M>
+where
+    A: 'static + Handler<M>,
+    M: Message,
+{
+    async fn recv(&mut self) -> Option<Received<A>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(published) if !self.accepts(&published.message) => continue,
+                Ok(published) => {
+                    return Some(Received::Message(Box::new(TellEnvelope::new(
+                        published.message,
+                        published.metadata,
+                    ))))
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    return Some(Received::Lagged(lagged::<M>(skipped)))
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    fn try_recv(&mut self) -> Option<Received<A>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(published) if !self.accepts(&published.message) => continue,
+                Ok(published) => {
+                    return Some(Received::Message(Box::new(TellEnvelope::new(
+                        published.message,
+                        published.metadata,
+                    ))))
+                }
+                Err(TryRecvError::Lagged(skipped)) => {
+                    return Some(Received::Lagged(lagged::<M>(skipped)))
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return None,
+            }
+        }
+    }
+
+    fn discard_pending(&mut self) -> usize {
+        let mut pending = 0;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(published) if self.accepts(&published.message) => pending += 1,
+                // Messages missed through lagging were never going to be
+                // handled, so they are not counted.
+                Ok(_) | Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return pending,
+            }
+        }
+    }
+}
+
+/// Turns a subscription into a stream that reports missed messages as
+/// errors, and ends once the channel closes.
+pub(crate) fn stream<M: Message>(
+    receiver: Receiver<M>,
+) -> impl Stream<Item = Result<M, Lagged>> + Send + 'static {
+    unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(published) => Some((Ok(published.message), receiver)),
+            Err(RecvError::Lagged(skipped)) => Some((Err(lagged::<M>(skipped)), receiver)),
+            Err(RecvError::Closed) => None,
+        }
+    })
+}
+
+fn lagged<M>(skipped: u64) -> Lagged {
+    Lagged {
+        message_type: type_name::<M>(),
+        skipped,
     }
 }
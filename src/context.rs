@@ -1,21 +1,496 @@
 use crate::{
-    channel::BroadcastChannel, error::ContextError, message::Message, publisher::Publisher,
+    actor::{closed, Actor, ActorAddress, ActorId, ChildTerminated, Terminated, TerminationReason},
+    channel::{BroadcastChannel, Published, Sender, Subscription},
+    envelope::{ChildTerminatedEnvelope, StreamFinishedEnvelope, TerminatedEnvelope},
+    error::{AddressError, ContextError},
+    handler::{
+        detail::{subscription, Tellable},
+        Handler,
+    },
+    mailbox::{ActorOptions, Owner},
+    message::Message,
+    metadata::{self, Headers, Metadata, Origin},
+    publisher::{detail::Publishable, Publisher},
+    queue::{MailboxSender, QueueError},
+    stream::{StreamFinished, StreamHandle, StreamId},
+    system::SystemHandle,
+    timer::TimerHandle,
+};
+use ::futures::{Stream, StreamExt};
+use ::std::{
+    any::TypeId, collections::HashMap, future::Future, marker::PhantomData, mem,
+    sync::atomic::AtomicPtr, time::Duration,
+};
+use ::tokio::{
+    spawn,
+    sync::mpsc,
+    task::AbortHandle,
+    time::{interval_at, sleep, sleep_until, Instant, MissedTickBehavior},
 };
-use ::std::{any::TypeId, collections::HashMap, marker::PhantomData, sync::atomic::AtomicPtr};
-use ::tokio::sync::broadcast::Sender;
 
 pub struct Context<A> {
+    /// The actor's own address, which a source does not have.
+    address: Option<ActorAddress<A>>,
     channels: HashMap<TypeId, Box<dyn BroadcastChannel>>,
+    children: Vec<Child>,
+    /// The metadata of the message being handled, if any.
+    envelope: Option<Metadata>,
+    stop: Option<(StopMode, TerminationReason)>,
+    /// Subscriptions made by the actor, for its mailbox to pick up.
+    subscriptions: Vec<Box<dyn Subscription<A>>>,
+    system: SystemHandle,
+    /// Timers and streams, which are cancelled when the actor stops.
+    tasks: Vec<AbortHandle>,
     _actor: PhantomData<AtomicPtr<A>>,
 }
 
+/// How an actor should stop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StopMode {
+    /// Stop without handling any more messages.
+    Immediate,
+    /// Stop accepting messages, and handle those already received first.
+    Drain,
+}
+
+/// A running child actor, as seen by its parent.
+struct Child {
+    id: ActorId,
+    stop: Box<dyn Fn(StopMode, TerminationReason) + Send>,
+    done: mpsc::Receiver<()>,
+}
+
 impl<A> Context<A> {
-    pub(crate) fn new(channels: HashMap<TypeId, Box<dyn BroadcastChannel>>) -> Self {
+    /// A context for a source, which has no mailbox.
+    pub(crate) fn new(
+        channels: HashMap<TypeId, Box<dyn BroadcastChannel>>,
+        system: SystemHandle,
+    ) -> Self {
         Context {
+            address: None,
             channels,
+            children: Vec::new(),
+            envelope: None,
+            stop: None,
+            subscriptions: Vec::new(),
+            system,
+            tasks: Vec::new(),
             _actor: PhantomData,
         }
     }
+
+    /// A context for the actor at `address`.
+    pub(crate) fn for_actor(
+        channels: HashMap<TypeId, Box<dyn BroadcastChannel>>,
+        system: SystemHandle,
+        address: ActorAddress<A>,
+    ) -> Self {
+        let mut context = Context::new(channels, system);
+        context.address = Some(address);
+        context
+    }
+
+    /// Completes once the system starts shutting down.
+    ///
+    /// Sources should return from [`Source::run`] soon after, since shutdown
+    /// waits for them before stopping any actor. The returned future does
+    /// not borrow the context, so it can be raced against work that does.
+    ///
+    /// [`Source::run`]: crate::prelude::Source::run
+    pub fn shutdown_requested(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut shutdown = self.system.shutdown_signal();
+        async move {
+            // An error means the system is gone, which is as good as
+            // shutting down.
+            let _ = shutdown.wait_for(|shutdown| *shutdown).await;
+        }
+    }
+
+    pub(crate) fn request_stop(&mut self, mode: StopMode, reason: TerminationReason) {
+        // A request to drain never overrides one to stop immediately.
+        if !matches!(self.stop, Some((StopMode::Immediate, _))) {
+            self.stop = Some((mode, reason));
+        }
+    }
+
+    pub(crate) fn stop_requested(&self) -> Option<StopMode> {
+        self.stop.map(|(mode, _)| mode)
+    }
+
+    /// Why the actor is stopping, if it was asked to.
+    pub(crate) fn stop_reason(&self) -> Option<TerminationReason> {
+        self.stop.map(|(_, reason)| reason)
+    }
+
+    /// The system this runs in, e.g. to [`lookup`](SystemHandle::lookup)
+    /// other actors by name.
+    pub fn system(&self) -> &SystemHandle {
+        &self.system
+    }
+
+    /// Publishes a message to every actor that handles it, see
+    /// [`Publish::publish`].
+    ///
+    /// This is also inherent so that publishing a message missing from the
+    /// `publish` attribute is reported through the diagnostics of
+    /// [`Publisher`].
+    pub fn publish<M>(&mut self, message: M) -> Result<(), ContextError>
+    where
+        M: Message + Publishable<A>,
+    {
+        self.publish_with(message, Headers::new())
+    }
+
+    /// Publishes a message like [`publish`](Context::publish), with
+    /// `headers` on top of those it inherits from the message being handled,
+    /// if any.
+    pub fn publish_with<M>(&mut self, message: M, headers: Headers) -> Result<(), ContextError>
+    where
+        M: Message + Publishable<A>,
+    {
+        if self.system.is_draining() {
+            return Err(ContextError::ShuttingDown);
+        }
+        self.channel::<M>()?
+            .send(Published::new(message, headers))
+            .map_err(|source| ContextError::BroadcastFailure {
+                source: source.into(),
+            })?;
+        Ok(())
+    }
+
+    /// Publishes a message at `at`, unless this has stopped by then.
+    pub fn publish_at<M>(&mut self, at: Instant, message: M) -> Result<TimerHandle, ContextError>
+    where
+        A: Publisher<M>,
+        M: Message,
+    {
+        let channel = self.channel::<M>()?.clone();
+        let system = self.system.clone();
+        let task = self.start_task(async move {
+            sleep_until(at).await;
+            if !system.is_draining() {
+                // There may be no subscribers, which is fine.
+                let _ = channel.send(Published::new(message, Headers::new()));
+            }
+        });
+        Ok(TimerHandle::new(task))
+    }
+
+    /// Publishes a message to `topic`, so that it reaches only the actors
+    /// subscribed to that topic, e.g. with `#[handle(M, topic = "...")]` or
+    /// [`subscribe_to`](Context::subscribe_to).
+    ///
+    /// Fails like [`Publish::publish`], e.g. when nothing is subscribed to
+    /// the topic.
+    pub fn publish_to<M>(&mut self, topic: &str, message: M) -> Result<(), ContextError>
+    where
+        A: Publisher<M>,
+        M: Message,
+    {
+        if self.system.is_draining() {
+            return Err(ContextError::ShuttingDown);
+        }
+        self.system
+            .publish_to(topic, message)
+            .map_err(|source| ContextError::BroadcastFailure { source })
+    }
+
+    fn channel<M: Message>(&self) -> Result<&Sender<M>, ContextError> {
+        self.channels
+            .get(&TypeId::of::<M>())
+            .ok_or(ContextError::ChannelLookupError)?
+            .as_any()
+            .downcast_ref::<Sender<M>>()
+            .ok_or(ContextError::ChannelLookupError)
+    }
+
+    fn start_task<F>(&mut self, task: F) -> AbortHandle
+    where
+        F: 'static + Future<Output = ()> + Send,
+    {
+        self.tasks.retain(|task| !task.is_finished());
+        let task = spawn(metadata::inherit_sender(task)).abort_handle();
+        self.tasks.push(task.clone());
+        task
+    }
+
+    /// Cancels the actor's timers and streams.
+    pub(crate) fn cancel_tasks(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+
+    /// The metadata of the message being handled: who sent it, how, and
+    /// when. `None` outside of handlers and responders, e.g. in
+    /// [`Actor::started`], and while handling a message the framework
+    /// generated, such as [`Actor::terminated`].
+    ///
+    /// Messages told, asked or published while handling it carry on its
+    /// correlation id and headers.
+    pub fn envelope(&self) -> Option<&Metadata> {
+        self.envelope.as_ref()
+    }
+
+    /// Records the metadata of the message about to be handled, or `None`
+    /// once it has been.
+    pub(crate) fn set_envelope(&mut self, envelope: Option<Metadata>) {
+        metadata::handling(envelope.as_ref());
+        self.envelope = envelope;
+    }
+
+    /// Hands over the subscriptions made since the last call.
+    pub(crate) fn take_subscriptions(&mut self) -> Vec<Box<dyn Subscription<A>>> {
+        mem::take(&mut self.subscriptions)
+    }
+
+    /// Forgets a child that terminated, returning `false` if it was not one
+    /// of this actor's, e.g. because it belonged to an instance that failed
+    /// and was restarted.
+    pub(crate) fn forget_child(&mut self, id: ActorId) -> bool {
+        let count = self.children.len();
+        self.children.retain(|child| child.id != id);
+        self.children.len() < count
+    }
+
+    /// Stops every child and waits for them to finish.
+    pub(crate) async fn stop_children(&mut self, mode: StopMode, reason: TerminationReason) {
+        for child in &self.children {
+            (child.stop)(mode, reason);
+        }
+        for mut child in self.children.drain(..) {
+            // TODO: log the error
+            let _ = child.done.recv().await;
+        }
+    }
+}
+
+impl<A> Drop for Context<A> {
+    fn drop(&mut self) {
+        self.cancel_tasks();
+    }
+}
+
+impl<A: 'static + Actor> Context<A> {
+    /// The id of this actor, the same as its address's.
+    pub fn id(&self) -> ActorId {
+        self.own_address().id()
+    }
+
+    /// The name this actor was added with, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.own_address().name()
+    }
+
+    /// Returns this actor's address, e.g. to give to another actor to reply
+    /// to.
+    pub fn address(&self) -> ActorAddress<A> {
+        self.own_address().clone()
+    }
+
+    /// Queues a message for this actor, to be handled after the messages
+    /// already in its mailbox.
+    ///
+    /// Fails like [`ActorAddress::tell`], e.g. when this actor's bounded
+    /// mailbox is full or it is stopping.
+    pub fn tell_self<M>(&self, message: M) -> Result<(), AddressError>
+    where
+        M: Message + Tellable<A>,
+    {
+        self.own_address().tell(message)
+    }
+
+    fn own_address(&self) -> &ActorAddress<A> {
+        self.address
+            .as_ref()
+            .expect("an actor's context always has its address")
+    }
+
+    fn sender(&self) -> Option<MailboxSender<A>> {
+        self.address
+            .as_ref()
+            .map(|address| address.sender().clone())
+    }
+
+    /// Stops this actor once the current message has been handled, running
+    /// its stop hooks. Messages still queued are dropped.
+    ///
+    /// The actor accepts no more messages, so later tells and asks fail with
+    /// [`AddressError::ActorStopped`].
+    ///
+    /// [`AddressError::ActorStopped`]: crate::error::AddressError::ActorStopped
+    pub fn stop(&mut self) {
+        if let Some(sender) = self.sender() {
+            sender.close_to_messages();
+        }
+        self.request_stop(StopMode::Immediate, TerminationReason::Stopped);
+    }
+
+    /// Tells this actor `message` once `delay` has passed.
+    pub fn tell_self_after<M>(&mut self, delay: Duration, message: M) -> TimerHandle
+    where
+        M: Message + Tellable<A>,
+    {
+        let sender = self.sender();
+        let task = self.start_task(async move {
+            sleep(delay).await;
+            if let Some(sender) = sender {
+                // The actor may have stopped, which is fine.
+                let metadata = Metadata::new(Origin::Direct, Headers::new());
+                let _ = sender.send(message.envelope(metadata)).await;
+            }
+        });
+        TimerHandle::new(task)
+    }
+
+    /// Tells this actor a message built by `factory` every `period`, starting
+    /// one `period` from now.
+    ///
+    /// Ticks missed while the mailbox is full are delayed rather than sent in
+    /// a burst.
+    pub fn run_interval<M, F>(&mut self, period: Duration, mut factory: F) -> TimerHandle
+    where
+        M: Message + Tellable<A>,
+        F: 'static + FnMut() -> M + Send,
+    {
+        let sender = self.sender();
+        let task = self.start_task(async move {
+            let sender = match sender {
+                Some(sender) => sender,
+                None => return,
+            };
+            let mut ticks = interval_at(Instant::now() + period, period);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let metadata = Metadata::new(Origin::Direct, Headers::new());
+                if let Err(QueueError::Closed) = sender.send(factory().envelope(metadata)).await {
+                    break;
+                }
+            }
+        });
+        TimerHandle::new(task)
+    }
+
+    /// Feeds every item of `stream` to this actor's handler, in order with
+    /// its other messages, then tells it through [`Actor::stream_finished`]
+    /// once the stream ends.
+    ///
+    /// Items wait for room in a bounded mailbox whose overflow policy is
+    /// `Block`. The stream is cancelled when this actor stops.
+    pub fn add_stream<S>(&mut self, stream: S) -> StreamHandle
+    where
+        S: 'static + Stream + Send,
+        S::Item: Message + Tellable<A>,
+    {
+        let id = StreamId::next();
+        let sender = self.sender();
+        let task = self.start_task(async move {
+            let sender = match sender {
+                Some(sender) => sender,
+                None => return,
+            };
+            let mut stream = Box::pin(stream);
+            while let Some(message) = stream.next().await {
+                let metadata = Metadata::new(Origin::Direct, Headers::new());
+                if let Err(QueueError::Closed) = sender.send(message.envelope(metadata)).await {
+                    return;
+                }
+            }
+            // The actor may have stopped, which is fine.
+            let _ = sender.force(Box::new(StreamFinishedEnvelope::new(StreamFinished {
+                stream: id,
+            })));
+        });
+        StreamHandle::new(id, task)
+    }
+
+    /// Subscribes this actor to the `M`s published to `topic`, on top of the
+    /// messages its `handle` attribute subscribes it to. The attribute's
+    /// filter for `M`, if any, applies to the topic too.
+    ///
+    /// This lets e.g. an actor per customer receive only that customer's
+    /// messages, published with [`publish_to`](Context::publish_to).
+    ///
+    /// The subscription ends if the actor is restarted, so an actor that
+    /// subscribes in [`Actor::started`] is subscribed once per instance.
+    pub fn subscribe_to<M>(&mut self, topic: &str) -> Result<(), ContextError>
+    where
+        A: Handler<M>,
+        M: Message,
+    {
+        let subscription = subscription::<A, M>(&self.system, topic)
+            .map_err(|source| ContextError::SubscribeFailure { source })?;
+        self.subscriptions.push(subscription);
+        Ok(())
+    }
+
+    /// Watches another actor, so that this actor is told through
+    /// [`Actor::terminated`] when it terminates. Watching an actor that has
+    /// already terminated reports it straight away.
+    ///
+    /// Like timers, the watch ends when this actor stops or is restarted.
+    pub fn watch<W: 'static + Actor>(&mut self, address: &ActorAddress<W>) {
+        let watcher = match self.sender() {
+            Some(sender) => sender,
+            None => return,
+        };
+        let id = address.id();
+        let termination = address.termination();
+        self.start_task(async move {
+            let reason = closed(termination).await;
+            // The watcher may already have stopped, which is fine.
+            let _ = watcher.force(Box::new(TerminatedEnvelope::new(Terminated { id, reason })));
+        });
+    }
+
+    /// Starts a child actor that is stopped when this actor stops.
+    ///
+    /// This actor is told when the child terminates through
+    /// [`Actor::child_terminated`].
+    pub fn spawn_child<C: 'static + Actor>(
+        &mut self,
+        actor: C,
+    ) -> Result<ActorAddress<C>, ContextError> {
+        self.spawn_child_with(ActorOptions::new(actor))
+    }
+
+    /// Like [`spawn_child`](Self::spawn_child), but starts the child with
+    /// `options`, e.g. under a name, with its own mailbox configuration, or
+    /// supervised.
+    pub fn spawn_child_with<C: 'static + Actor>(
+        &mut self,
+        options: ActorOptions<C>,
+    ) -> Result<ActorAddress<C>, ContextError> {
+        let parent = self.sender();
+        let notify_parent = move |terminated: ChildTerminated| {
+            if let Some(parent) = parent {
+                // The parent may already have stopped, which is fine.
+                let _ = parent.force(Box::new(ChildTerminatedEnvelope::new(terminated)));
+            }
+        };
+
+        let name = options.registered_name();
+        self.system
+            .reserve(name.as_deref())
+            .map_err(|source| ContextError::RegisterFailure { source })?;
+        let (address, done) = self
+            .system
+            .start_actor(options, Owner::Parent(Box::new(notify_parent)))
+            .map_err(|source| ContextError::SpawnFailure { source })?;
+
+        let child = address.clone();
+        self.children.push(Child {
+            id: address.id(),
+            stop: Box::new(move |mode, reason| child.request_stop(mode, reason)),
+            done,
+        });
+        self.system
+            .register_started(name, &address)
+            .map_err(|source| ContextError::RegisterFailure { source })?;
+        Ok(address)
+    }
 }
 
 pub trait Publish<M: Message> {
@@ -28,20 +503,6 @@ where
     M: Message,
 {
     fn publish(&mut self, message: M) -> Result<(), ContextError> {
-        let type_id = TypeId::of::<M>();
-        let channel = self
-            .channels
-            .get(&type_id)
-            .ok_or(ContextError::ChannelLookupError)?
-            .as_any()
-            .downcast_ref::<Sender<M>>()
-            .ok_or(ContextError::ChannelLookupError)?;
-
-        channel
-            .send(message)
-            .map_err(|source| ContextError::BroadcastFailure {
-                source: source.into(),
-            })?;
-        Ok(())
+        Context::publish(self, message)
     }
 }
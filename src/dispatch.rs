@@ -0,0 +1,58 @@
+use crate::message::Message;
+
+/// An enum of related messages, whose variants are each handled by their own
+/// method of an [`actor`](macro@crate::actor) impl.
+///
+/// Implemented by the [`Dispatch`](::yaaf_macros::Dispatch) derive. The enum
+/// is listed in a `handle` attribute of the impl with the `dispatch` option,
+/// and each variant gets a method marked `#[handler(variant = Variant)]`,
+/// which takes the variant's fields, in order, after the context:
+///
+/// ```rust
+/// # use ::yaaf::prelude::*;
+/// #[derive(Clone, Debug, Dispatch)]
+/// enum Command {
+///     Deposit { amount: u64 },
+///     Withdraw(u64),
+///     Close,
+/// }
+///
+/// struct Account {
+///     balance: u64,
+///     open: bool,
+/// }
+///
+/// #[actor]
+/// #[handle(Command, dispatch)]
+/// impl Account {
+///     #[handler(variant = Deposit)]
+///     async fn deposit(&mut self, _ctx: &mut Context<Self>, amount: u64) {
+///         self.balance += amount;
+///     }
+///
+///     #[handler(variant = Withdraw)]
+///     async fn withdraw(&mut self, _ctx: &mut Context<Self>, amount: u64) {
+///         self.balance -= amount;
+///     }
+///
+///     #[handler(variant = Close)]
+///     async fn close(&mut self, _ctx: &mut Context<Self>) {
+///         self.open = false;
+///     }
+/// }
+/// ```
+///
+/// Each variant is then told on its own, e.g.
+/// `account.tell(Command::Deposit { amount: 100 })`. A variant without a
+/// method fails to compile. If the impl dispatches several enums, a method
+/// names the one its variant belongs to, as in
+/// `#[handler(Command, variant = Deposit)]`.
+pub trait Dispatch: Message {
+    /// The variants with their fields in order, for the `actor` attribute to
+    /// match on.
+    #[doc(hidden)]
+    type Variants;
+
+    #[doc(hidden)]
+    fn variants(self) -> Self::Variants;
+}
@@ -0,0 +1,175 @@
+use crate::{
+    actor::{Actor, ChildTerminated, Terminated, TerminationReason},
+    context::{Context, StopMode},
+    handler::Handler,
+    lag::Lagged,
+    message::Message,
+    metadata::Metadata,
+    responder::Responder,
+    stream::StreamFinished,
+};
+use ::async_trait::async_trait;
+use ::tokio::sync::oneshot;
+
+/// A type-erased message waiting in an actor's mailbox.
+#[async_trait]
+pub trait Envelope<A>: Send {
+    async fn deliver(self: Box<Self>, actor: &mut A, ctx: &mut Context<A>);
+
+    /// Takes the metadata of a told, asked or published message, which the
+    /// actor can look at while handling it.
+    fn take_metadata(&mut self) -> Option<Metadata> {
+        None
+    }
+}
+
+pub(crate) struct TellEnvelope<M> {
+    message: M,
+    metadata: Option<Metadata>,
+}
+
+impl<M> TellEnvelope<M> {
+    pub(crate) fn new(message: M, metadata: Metadata) -> Self {
+        TellEnvelope {
+            message,
+            metadata: Some(metadata),
+        }
+    }
+}
+
+#[async_trait]
+impl<A, M> Envelope<A> for TellEnvelope<M>
+where
+    A: Handler<M>,
+    M: Message,
+{
+    async fn deliver(self: Box<Self>, actor: &mut A, ctx: &mut Context<A>) {
+        actor.handle(ctx, self.message).await;
+    }
+
+    fn take_metadata(&mut self) -> Option<Metadata> {
+        self.metadata.take()
+    }
+}
+
+pub(crate) struct AskEnvelope<M, R> {
+    message: M,
+    metadata: Option<Metadata>,
+    reply: oneshot::Sender<R>,
+}
+
+impl<M, R> AskEnvelope<M, R> {
+    pub(crate) fn new(message: M, metadata: Metadata, reply: oneshot::Sender<R>) -> Self {
+        AskEnvelope {
+            message,
+            metadata: Some(metadata),
+            reply,
+        }
+    }
+}
+
+#[async_trait]
+impl<A, M> Envelope<A> for AskEnvelope<M, A::Response>
+where
+    A: Responder<M>,
+    M: Message,
+{
+    async fn deliver(self: Box<Self>, actor: &mut A, ctx: &mut Context<A>) {
+        let response = actor.respond(ctx, self.message).await;
+        // The asker may have given up waiting, which is fine.
+        let _ = self.reply.send(response);
+    }
+
+    fn take_metadata(&mut self) -> Option<Metadata> {
+        self.metadata.take()
+    }
+}
+
+/// Stops the actor once every message queued before it has been handled.
+pub(crate) struct StopEnvelope {
+    mode: StopMode,
+    reason: TerminationReason,
+}
+
+impl StopEnvelope {
+    pub(crate) fn new(mode: StopMode, reason: TerminationReason) -> Self {
+        StopEnvelope { mode, reason }
+    }
+}
+
+#[async_trait]
+impl<A: Actor> Envelope<A> for StopEnvelope {
+    async fn deliver(self: Box<Self>, _actor: &mut A, ctx: &mut Context<A>) {
+        ctx.request_stop(self.mode, self.reason);
+    }
+}
+
+pub(crate) struct ChildTerminatedEnvelope {
+    terminated: ChildTerminated,
+}
+
+impl ChildTerminatedEnvelope {
+    pub(crate) fn new(terminated: ChildTerminated) -> Self {
+        ChildTerminatedEnvelope { terminated }
+    }
+}
+
+#[async_trait]
+impl<A: Actor> Envelope<A> for ChildTerminatedEnvelope {
+    async fn deliver(self: Box<Self>, actor: &mut A, ctx: &mut Context<A>) {
+        if ctx.forget_child(self.terminated.id) {
+            actor.child_terminated(ctx, self.terminated).await;
+        }
+    }
+}
+
+pub(crate) struct LaggedEnvelope {
+    lagged: Lagged,
+}
+
+impl LaggedEnvelope {
+    pub(crate) fn new(lagged: Lagged) -> Self {
+        LaggedEnvelope { lagged }
+    }
+}
+
+#[async_trait]
+impl<A: Actor> Envelope<A> for LaggedEnvelope {
+    async fn deliver(self: Box<Self>, actor: &mut A, ctx: &mut Context<A>) {
+        actor.lagged(ctx, self.lagged).await;
+    }
+}
+
+pub(crate) struct TerminatedEnvelope {
+    terminated: Terminated,
+}
+
+impl TerminatedEnvelope {
+    pub(crate) fn new(terminated: Terminated) -> Self {
+        TerminatedEnvelope { terminated }
+    }
+}
+
+#[async_trait]
+impl<A: Actor> Envelope<A> for TerminatedEnvelope {
+    async fn deliver(self: Box<Self>, actor: &mut A, ctx: &mut Context<A>) {
+        actor.terminated(ctx, self.terminated).await;
+    }
+}
+
+pub(crate) struct StreamFinishedEnvelope {
+    finished: StreamFinished,
+}
+
+impl StreamFinishedEnvelope {
+    pub(crate) fn new(finished: StreamFinished) -> Self {
+        StreamFinishedEnvelope { finished }
+    }
+}
+
+#[async_trait]
+impl<A: Actor> Envelope<A> for StreamFinishedEnvelope {
+    async fn deliver(self: Box<Self>, actor: &mut A, ctx: &mut Context<A>) {
+        actor.stream_finished(ctx, self.finished).await;
+    }
+}
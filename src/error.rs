@@ -1,3 +1,4 @@
+use crate::actor::ActorId;
 use ::thiserror::Error;
 use ::tokio::sync::{broadcast, mpsc};
 
@@ -9,14 +10,18 @@ pub enum YaafError {
     ContextError(#[from] ContextError),
     #[error(transparent)]
     SystemError(#[from] SystemError),
+    #[error(transparent)]
+    RegistryError(#[from] RegistryError),
 }
 
 #[derive(Debug, Error)]
 pub enum AddressError {
-    #[error("failed to find channel")]
-    ChannelLookupError,
-    #[error("failed to tell actor")]
-    TellFailure { source: YaafInternalError },
+    #[error("actor stopped before responding")]
+    NoResponse,
+    #[error("actor mailbox is full")]
+    MailboxFull,
+    #[error("actor has stopped")]
+    ActorStopped,
 }
 
 #[derive(Debug, Error)]
@@ -25,6 +30,14 @@ pub enum ContextError {
     ChannelLookupError,
     #[error("failed to broadcast message")]
     BroadcastFailure { source: YaafInternalError },
+    #[error("failed to spawn child actor")]
+    SpawnFailure { source: YaafInternalError },
+    #[error("failed to register child actor")]
+    RegisterFailure { source: RegistryError },
+    #[error("failed to subscribe to topic")]
+    SubscribeFailure { source: YaafInternalError },
+    #[error("system is shutting down")]
+    ShuttingDown,
 }
 
 #[derive(Debug, Error)]
@@ -37,6 +50,39 @@ pub enum SystemError {
     CreateError { source: YaafInternalError },
     #[error("failed to add actor")]
     ShutdownError { source: YaafInternalError },
+    #[error("{id:?} is not a top level actor of this system")]
+    UnknownActor { id: ActorId },
+    #[error("{dependent:?} depending on {dependency:?} would create a cycle")]
+    DependencyCycle {
+        dependent: ActorId,
+        dependency: ActorId,
+    },
+    #[error("failed to register actor")]
+    RegisterFailure { source: RegistryError },
+    #[error("failed to subscribe to messages")]
+    SubscribeFailure { source: YaafInternalError },
+    #[error("failed to publish message")]
+    PublishFailure { source: YaafInternalError },
+    #[error("system is shutting down")]
+    ShuttingDown,
+}
+
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    #[error("an actor is already registered as {name:?}")]
+    NameTaken { name: String },
+    #[error("no actor is registered as {name:?}")]
+    NotRegistered { name: String },
+    #[error("{name:?} is registered as {actual}, not {expected}")]
+    WrongType {
+        name: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+    #[error("actor has stopped")]
+    ActorStopped,
+    #[error("failed to access registry")]
+    AccessFailure { source: YaafInternalError },
 }
 
 #[derive(Debug, Error)]
@@ -53,6 +99,8 @@ pub enum YaafInternalError {
     ChannelLookupFailure,
     #[error("failed to send")]
     SendFailure,
+    #[error("failed to lock shared state")]
+    LockFailure,
 }
 
 impl<T> From<broadcast::error::SendError<T>> for YaafInternalError {
@@ -67,6 +115,8 @@ impl<T> From<mpsc::error::SendError<T>> for YaafInternalError {
     }
 }
 
+// Kept for compatibility, although tokio no longer returns this error.
+#[allow(deprecated)]
 impl From<mpsc::error::RecvError> for YaafInternalError {
     fn from(_src: mpsc::error::RecvError) -> Self {
         YaafInternalError::ReceiveFailure
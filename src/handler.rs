@@ -2,8 +2,57 @@ use crate::{actor::Actor, context::Context, message::Message};
 use ::async_trait::async_trait;
 
 #[doc(hidden)]
-pub trait HandlerRegistered<M: Message> {}
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not declare `#[handle({M})]`",
+    label = "`{M}` is not handled by `{Self}`",
+    note = "add `{M}` to a `handle` or `subscribe` attribute of `{Self}`, or handle it with a `#[handler]` method"
+)]
+pub trait HandlerRegistered<M: Message> {
+    /// Which published messages the actor receives, set by the `handle`
+    /// attribute of the [`Actor`](::yaaf_macros::Actor) derive.
+    fn route() -> Route<M> {
+        Route::default()
+    }
+}
+
+/// Implemented for the message types an actor accepts through
+/// [`Tell`](crate::Tell), i.e. those in its `handle` attribute but not those
+/// only in its `subscribe` attribute.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be told `{M}`",
+    label = "`{M}` is not in a `handle` attribute of `{Self}`",
+    note = "add `{M}` to a `handle` attribute of `{Self}` to tell it, since those only in a `subscribe` attribute are received when published"
+)]
+pub trait DirectRegistered<M: Message> {}
+
+/// Which published messages of type `M` an actor receives.
+#[doc(hidden)]
+pub struct Route<M> {
+    /// Whether to receive published messages at all, which `direct_only`
+    /// turns off.
+    pub subscribe: bool,
+    /// The topics to subscribe to, or every message of type `M` if empty.
+    pub topics: &'static [&'static str],
+    /// Drops the messages for which this returns `false`.
+    pub filter: Option<fn(&M) -> bool>,
+}
+
+impl<M> Default for Route<M> {
+    fn default() -> Self {
+        Route {
+            subscribe: true,
+            topics: &[],
+            filter: None,
+        }
+    }
+}
 
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not handle `{M}`",
+    label = "`{Self}` has no `Handler<{M}>`",
+    note = "implement `Handler<{M}>` for `{Self}`, or add a `#[handler]` method taking `{M}` to its `#[actor]` impl"
+)]
 #[async_trait]
 pub trait Handler<M: Message>: Actor + HandlerRegistered<M> + Send {
     async fn handle(&mut self, ctx: &mut Context<Self>, message: M);
@@ -12,79 +61,70 @@ pub trait Handler<M: Message>: Actor + HandlerRegistered<M> + Send {
 pub(crate) mod detail {
     use super::*;
     use crate::{
-        actor::Actor,
-        channel::{BroadcastChannel, DirectChannel},
+        channel::{Filtered, Subscription},
+        envelope::{Envelope, TellEnvelope},
         error::YaafInternalError,
-        mailbox::Mailbox,
-        message::{detail::MessageList, Message, SystemMessage},
+        message::Message,
+        metadata::Metadata,
+        system::SystemHandle,
     };
-    use ::async_trait::async_trait;
-    use std::{any::TypeId, collections::HashMap, sync::Arc};
-    use tokio::sync::{broadcast::Sender, mpsc::Receiver, Mutex};
 
-    #[async_trait]
-    pub trait HandlesList<ML: MessageList + ?Sized> {
-        async fn setup_mailboxes(
-            self,
-            handle_channels: &HashMap<TypeId, Box<dyn BroadcastChannel>>,
-            publish_channels: &HashMap<TypeId, Box<dyn BroadcastChannel>>,
-            system_channel: Sender<SystemMessage>,
-        ) -> Result<(HashMap<TypeId, Box<dyn DirectChannel>>, Vec<Receiver<()>>), YaafInternalError>;
+    /// `A: Handler<M> + DirectRegistered<M>`, with `M` first so that it is
+    /// only checked once `M` is known. Otherwise an actor with one handler
+    /// has `M` inferred from it, and telling it anything else is reported as
+    /// a mismatched type rather than a missing handler.
+    pub trait Tellable<A> {
+        fn envelope(self, metadata: Metadata) -> Box<dyn Envelope<A>>;
     }
 
-    macro_rules! start_mailbox {
-        ( $actor:ident, $system_channel:ident, $handle_channels:ident, $publish_channels:ident, $direct_channels:ident, $done:ident, $head:ident, $( $tail:ident, )* ) => {
-            let type_id = TypeId::of::<$head>();
-            let channel = $handle_channels
-                .get(&type_id)
-                .ok_or(YaafInternalError::ChannelLookupFailure)?
-                .as_any()
-                .downcast_ref::<Sender<$head>>()
-                .ok_or(YaafInternalError::ChannelLookupFailure)?;
-            let (tell, done) = Mailbox::start($actor.clone(), channel.subscribe(), $system_channel.subscribe(), $publish_channels.clone()).await?;
-            $done.push(done);
-            $direct_channels.insert(type_id, Box::new(tell));
-
-            start_mailbox!($actor, $system_channel, $handle_channels, $publish_channels, $direct_channels, $done, $( $tail, )*);
-        };
-        ($actor:ident, $system_channel:ident, $handle_channels:ident, $publish_channels:ident, $direct_channels:ident, $done:ident,) => {};
+    impl<A, M> Tellable<A> for M
+    where
+        A: Handler<M> + DirectRegistered<M>,
+        M: Message,
+    {
+        fn envelope(self, metadata: Metadata) -> Box<dyn Envelope<A>> {
+            Box::new(TellEnvelope::new(self, metadata))
+        }
     }
 
-    macro_rules! impl_handles_list {
-        ( $head:ident, $( $tail:ident, )* ) => {
-            #[async_trait]
-            impl<A, $head, $( $tail ),*> HandlesList<($head, $( $tail ),*)> for A
-            where
-                A: 'static + Actor + Handler<$head>$( + Handler<$tail>)*,
-                $head: Message, $( $tail: Message ),*
-            {
-                async fn setup_mailboxes(
-                    self,
-                    handle_channels: &HashMap<TypeId, Box<dyn BroadcastChannel>>,
-                    publish_channels: &HashMap<TypeId, Box<dyn BroadcastChannel>>,
-                    system_channel: Sender<SystemMessage>,
-                ) -> Result<(HashMap<TypeId, Box<dyn DirectChannel>>, Vec<Receiver<()>>), YaafInternalError> {
-                    let actor = Arc::new(Mutex::new(self));
-                    let mut direct_channels: HashMap<TypeId, Box<dyn DirectChannel>> = HashMap::new();
-                    let mut done = Vec::new();
-                    start_mailbox!(
-                        actor,
-                        system_channel,
-                        handle_channels,
-                        publish_channels,
-                        direct_channels,
-                        done,
-                        $head,
-                        $( $tail, )*
-                    );
-                    Ok((direct_channels, done))
-                }
-            }
+    /// An actor's subscriptions to the channels it receives from.
+    pub type Subscriptions<A> = Vec<Box<dyn Subscription<A>>>;
 
-            impl_handles_list!($( $tail, )*);
-        };
-        () => {};
+    /// Subscribes to the channels `A`'s route for `M` names.
+    pub fn subscriptions<A, M>(system: &SystemHandle) -> Result<Subscriptions<A>, YaafInternalError>
+    where
+        A: 'static + Handler<M>,
+        M: Message,
+    {
+        let route = <A as HandlerRegistered<M>>::route();
+        if !route.subscribe {
+            return Ok(Vec::new());
+        }
+        if route.topics.is_empty() {
+            let channel = system.channel::<M>()?;
+            return Ok(vec![Box::new(Filtered::new(
+                channel.subscribe(),
+                route.filter,
+            ))]);
+        }
+        route
+            .topics
+            .iter()
+            .map(|topic| subscription::<A, M>(system, topic))
+            .collect()
     }
 
-    impl_handles_list!(M10, M9, M8, M7, M6, M5, M4, M3, M2, M1,);
+    /// Subscribes to one topic, applying `A`'s filter for `M`.
+    pub(crate) fn subscription<A, M>(
+        system: &SystemHandle,
+        topic: &str,
+    ) -> Result<Box<dyn Subscription<A>>, YaafInternalError>
+    where
+        A: 'static + Handler<M>,
+        M: Message,
+    {
+        let filter = <A as HandlerRegistered<M>>::route().filter;
+        let receiver = system.topic_receiver::<M>(topic)?;
+        Ok(Box::new(Filtered::new(receiver, filter)))
+    }
 }
@@ -0,0 +1,37 @@
+use ::thiserror::Error;
+
+/// What an actor does when it falls behind one of the broadcast channels it
+/// subscribes to, and so misses published messages.
+///
+/// Every lag is counted in the actor's [`ActorMetrics`] regardless of the
+/// policy.
+///
+/// [`ActorMetrics`]: crate::ActorMetrics
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Only count the missed messages.
+    #[default]
+    Count,
+    /// Deliver a [`Lagged`] notification to [`Actor::lagged`].
+    ///
+    /// [`Actor::lagged`]: crate::prelude::Actor::lagged
+    Notify,
+    /// Treat the lag as a failure, which the actor's supervisor handles like
+    /// a panic.
+    Fail,
+}
+
+/// Delivered to [`Actor::lagged`] when an actor with [`LagPolicy::Notify`]
+/// misses published messages, and yielded as an error by the streams of
+/// [`System::subscribe`].
+///
+/// [`Actor::lagged`]: crate::prelude::Actor::lagged
+/// [`System::subscribe`]: crate::prelude::System::subscribe
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("missed {skipped} messages of type {message_type}")]
+pub struct Lagged {
+    /// The name of the message type that was missed.
+    pub message_type: &'static str,
+    /// How many messages were missed.
+    pub skipped: u64,
+}
@@ -7,6 +7,19 @@
 //! # Key Features
 //!
 //! - Compile time checks on message publishing.
+//! - Request/response messaging via [`Ask`] and [`Responder`].
+//! - Bounded mailboxes with configurable overflow policies via [`MailboxConfig`].
+//! - Configurable broadcast capacities via [`SystemBuilder`], with a
+//!   [`LagPolicy`] for actors that fall behind.
+//! - Graceful shutdown that drains queued messages, see [`ShutdownMode`].
+//! - A registry for finding actors by name, see [`SystemHandle::lookup`].
+//! - Partitioned pub/sub through topics, see [`Context::publish_to`].
+//! - Enums of messages handled by a method per variant, see [`Dispatch`].
+//! - Handlers written as plain methods with the [`actor`](macro@actor) attribute.
+//! - Messages can be limited to direct tells with `#[handle(M, direct_only)]`,
+//!   or to published messages with `#[subscribe(M)]`.
+//! - Message metadata such as the sender and a correlation id, see
+//!   [`Context::envelope`].
 //! - Simple UX.
 //!
 //! ## Example
@@ -89,20 +102,46 @@
 mod actor;
 mod channel;
 mod context;
+mod dispatch;
+mod envelope;
 mod handler;
+mod lag;
 mod mailbox;
 mod message;
+mod metadata;
+mod metrics;
 mod publisher;
+mod queue;
+mod registry;
+mod responder;
 mod source;
+mod stream;
+mod supervision;
 mod system;
+mod timer;
 
 pub mod error;
 pub mod prelude;
 
-pub use crate::actor::ActorAddress;
-pub use crate::handler::HandlerRegistered;
+pub use crate::actor::{
+    ActorAddress, ActorId, ActorMeta, ChildTerminated, Terminated, TerminationReason,
+};
+#[doc(hidden)]
+pub use crate::handler::detail::{subscriptions, Subscriptions};
+pub use crate::handler::{DirectRegistered, HandlerRegistered, Route};
+pub use crate::lag::{LagPolicy, Lagged};
+pub use crate::mailbox::ActorOptions;
+#[doc(hidden)]
+pub use crate::message::detail::Cons;
 pub use crate::message::Message;
+pub use crate::metadata::{CorrelationId, Headers, Metadata, Origin};
+pub use crate::metrics::ActorMetrics;
 #[doc(inline)]
 pub use crate::prelude::*;
 pub use crate::publisher::Publisher;
+pub use crate::queue::{MailboxConfig, OverflowPolicy};
 pub use crate::source::SourceMeta;
+pub use crate::stream::{StreamFinished, StreamHandle, StreamId};
+pub use crate::supervision::{SupervisionStrategy, MAX_FAILED_STARTS};
+pub use crate::system::{ShutdownMode, ShutdownReport, SystemBuilder, SystemHandle};
+pub use crate::timer::TimerHandle;
@@ -1,76 +1,510 @@
 use crate::{
-    actor::Actor,
-    channel::BroadcastChannel,
-    context::Context,
-    error::YaafInternalError,
-    handler::Handler,
-    message::{Message, SystemMessage},
+    actor::{Actor, ActorAddress, ActorId, ChildTerminated, TerminationReason},
+    channel::{BroadcastChannel, Received, Subscription},
+    context::{Context, StopMode},
+    envelope::{Envelope, LaggedEnvelope},
+    lag::{LagPolicy, Lagged},
+    metadata::sent_by,
+    metrics::MetricsRecorder,
+    queue::{queue, MailboxConfig, MailboxReceiver},
+    supervision::{SupervisionStrategy, Supervisor, MAX_FAILED_STARTS},
+    system::SystemHandle,
+};
+use ::futures::{
+    future::{pending, select_all},
+    FutureExt,
+};
+use ::std::{
+    any::TypeId, collections::HashMap, panic::AssertUnwindSafe, sync::Arc, time::Duration,
 };
-use ::std::{any::TypeId, collections::HashMap, sync::Arc};
 use ::tokio::{
     select, spawn,
-    sync::{broadcast, mpsc, Mutex},
+    sync::{
+        mpsc::{self, error::TryRecvError},
+        watch,
+    },
+    task::yield_now,
+    time::sleep,
 };
 
-pub(crate) struct Mailbox<A: Actor + Handler<M>, M: Message> {
+/// Who is responsible for stopping an actor.
+pub(crate) enum Owner {
+    /// Stopped by [`System::shutdown`](crate::System::shutdown), through a
+    /// channel that bypasses the mailbox.
+    System(mpsc::UnboundedReceiver<StopMode>),
+    /// Stopped by its parent, which is told when the actor terminates.
+    Parent(Box<dyn FnOnce(ChildTerminated) + Send>),
+}
+
+/// How to start an actor, for [`System::add_actor_with`] and
+/// [`Context::spawn_child_with`].
+///
+/// The options combine freely, e.g. a supervised actor can also be named and
+/// given a bounded mailbox:
+///
+/// ```rust
+/// # use ::yaaf::{prelude::*, ActorOptions, MailboxConfig, OverflowPolicy, SupervisionStrategy};
+/// #[derive(Actor)]
+/// struct Worker;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn ::std::error::Error>> {
+/// let mut system = System::new();
+/// let worker = system
+///     .add_actor_with(
+///         ActorOptions::supervised(|| Worker, SupervisionStrategy::Restart)
+///             .name("worker")
+///             .mailbox(MailboxConfig::bounded(16, OverflowPolicy::Block)),
+///     )
+///     .await?;
+/// assert_eq!(Some("worker"), worker.name());
+/// # system.shutdown().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`System::add_actor_with`]: crate::System::add_actor_with
+/// [`Context::spawn_child_with`]: crate::Context::spawn_child_with
+pub struct ActorOptions<A> {
+    actor: A,
+    config: MailboxConfig,
+    name: Option<Arc<str>>,
+    supervisor: Supervisor<A>,
+}
+
+impl<A: Actor> ActorOptions<A> {
+    /// An unnamed, unsupervised actor with the mailbox its `mailbox`
+    /// attribute declares.
+    pub fn new(actor: A) -> Self {
+        ActorOptions {
+            actor,
+            config: A::mailbox_config(),
+            name: None,
+            supervisor: Supervisor::unsupervised(),
+        }
+    }
+
+    /// An actor that is rebuilt by `factory` according to `strategy`
+    /// whenever one of its handlers panics.
+    pub fn supervised<F>(mut factory: F, strategy: SupervisionStrategy) -> Self
+    where
+        F: 'static + FnMut() -> A + Send,
+    {
+        let actor = factory();
+        ActorOptions {
+            supervisor: Supervisor::new(factory, strategy),
+            ..Self::new(actor)
+        }
+    }
+
+    /// Registers the actor under `name`, so that it can be found with
+    /// [`SystemHandle::lookup`] until it terminates. The name also shows up
+    /// in its address's `Debug` output and through [`ActorAddress::name`].
+    ///
+    /// Starting the actor fails with [`RegistryError::NameTaken`] if a
+    /// running actor is already registered under `name`.
+    ///
+    /// [`RegistryError::NameTaken`]: crate::error::RegistryError::NameTaken
+    pub fn name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Overrides the mailbox configuration declared by the actor's `mailbox`
+    /// attribute.
+    pub fn mailbox(mut self, config: MailboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub(crate) fn registered_name(&self) -> Option<Arc<str>> {
+        self.name.clone()
+    }
+}
+
+/// The single task that owns an actor.
+///
+/// Direct messages from every sender share one queue, so they are handled in
+/// the order they were sent regardless of their type.
+pub(crate) struct Mailbox<A: Actor> {
+    abort: watch::Receiver<bool>,
+    actor: A,
     context: Context<A>,
     done: mpsc::Sender<()>,
-    handler: Arc<Mutex<A>>,
-    recv_broadcast: broadcast::Receiver<M>,
-    recv_system: broadcast::Receiver<SystemMessage>,
-    recv_tell: mpsc::UnboundedReceiver<M>,
+    failure: TerminationReason,
+    id: ActorId,
+    lag_policy: LagPolicy,
+    metrics: Arc<MetricsRecorder>,
+    parent: Option<Box<dyn FnOnce(ChildTerminated) + Send>>,
+    recv_stop: Option<mpsc::UnboundedReceiver<StopMode>>,
+    recv_tell: MailboxReceiver<A>,
+    /// The subscriptions from the actor's attributes.
+    subscriptions: Vec<Box<dyn Subscription<A>>>,
+    /// The subscriptions the actor made itself, e.g. through
+    /// [`Context::subscribe_to`], which do not outlive a restart.
+    subscribed: Vec<Box<dyn Subscription<A>>>,
+    supervisor: Supervisor<A>,
+    termination: watch::Sender<Option<TerminationReason>>,
 }
 
-impl<A: 'static + Actor + Handler<M>, M: Message> Mailbox<A, M> {
-    pub(crate) async fn start(
-        actor: Arc<Mutex<A>>,
-        recv_broadcast: broadcast::Receiver<M>,
-        recv_system: broadcast::Receiver<SystemMessage>,
+impl<A: 'static + Actor> Mailbox<A> {
+    /// Spawns the actor's task.
+    pub(crate) fn start(
+        options: ActorOptions<A>,
+        subscriptions: Vec<Box<dyn Subscription<A>>>,
         publish_channels: HashMap<TypeId, Box<dyn BroadcastChannel>>,
-    ) -> Result<(mpsc::UnboundedSender<M>, mpsc::Receiver<()>), YaafInternalError> {
+        system: SystemHandle,
+        owner: Owner,
+    ) -> (ActorAddress<A>, mpsc::Receiver<()>) {
+        let ActorOptions {
+            actor,
+            config,
+            name,
+            supervisor,
+        } = options;
         let (done, result) = mpsc::channel(1);
-        let (send_tell, recv_tell) = mpsc::unbounded_channel();
+        let (send_tell, recv_tell) = queue(config);
+        let id = ActorId::next();
+        let metrics = Arc::new(MetricsRecorder::default());
+        let (termination, recv_termination) = watch::channel(None);
+        let (recv_stop, parent) = match owner {
+            Owner::System(recv_stop) => (Some(recv_stop), None),
+            Owner::Parent(parent) => (None, Some(parent)),
+        };
 
-        let context = Context::new(publish_channels);
+        let address = ActorAddress::new(id, name, send_tell, metrics.clone(), recv_termination);
+        let abort = system.abort_signal();
+        let context = Context::for_actor(publish_channels, system, address.clone());
         let mailbox = Mailbox {
+            abort,
+            actor,
             context,
             done,
-            handler: actor,
-            recv_broadcast,
-            recv_system,
+            failure: TerminationReason::Panicked,
+            id,
+            lag_policy: A::lag_policy(),
+            metrics,
+            parent,
+            recv_stop,
             recv_tell,
+            subscriptions,
+            subscribed: Vec::new(),
+            supervisor,
+            termination,
         };
 
-        spawn(mailbox.run());
-        Ok((send_tell, result))
+        spawn(sent_by(id, mailbox.run()));
+        (address, result)
     }
 
     async fn run(mut self) {
-        loop {
+        let abort = self.abort.clone();
+        let processed = select! {
+            running = self.process() => Some(running),
+            () = signalled(abort) => None,
+        };
+        let running = match processed {
+            Some(running) => running,
+            None => {
+                self.failure = TerminationReason::Aborted;
+                false
+            }
+        };
+
+        let dropped = self.recv_tell.pending_messages()
+            + self
+                .subscriptions
+                .iter_mut()
+                .chain(&mut self.subscribed)
+                .map(|subscription| subscription.discard_pending())
+                .sum::<usize>();
+        if dropped > 0 {
+            self.context.system().record_dropped(self.id, dropped);
+        }
+        // Unsubscribe before reporting termination, so that publishing to a
+        // topic only a stopped actor subscribed to fails straight away.
+        self.subscriptions.clear();
+        self.subscribed.clear();
+        self.context.take_subscriptions();
+
+        let reason = if running {
+            self.context
+                .stop_reason()
+                .unwrap_or(TerminationReason::Stopped)
+        } else {
+            self.failure
+        };
+        if let Some(parent) = self.parent.take() {
+            parent(ChildTerminated {
+                id: self.id,
+                reason,
+            });
+        }
+        // Forget its names first, so that they are free once it is closed.
+        self.context.system().unregister(self.id);
+        self.termination.send_replace(Some(reason));
+
+        // TODO: log the error
+        let _ = self.done.send(()).await;
+    }
+
+    /// Runs the actor until it stops, returning `false` if it failed and
+    /// could not be restarted.
+    async fn process(&mut self) -> bool {
+        let mut running = self.started().await;
+
+        while running && self.context.stop_requested().is_none() {
+            self.subscribed.extend(self.context.take_subscriptions());
+            // A stop takes priority over the messages that are ready.
+            if let Some(mode) = try_recv_stop(&mut self.recv_stop) {
+                self.context.request_stop(mode, TerminationReason::Shutdown);
+                break;
+            }
             select! {
-                received = self.recv_system.recv() => {
-                    if let Ok(message) = received {
-                        match message {
-                            SystemMessage::Shutdown => {
-                                // TODO: log the error
-                                let _ = self.done.send(()).await;
-                                break;
-                            }
-                        }
-                    }
+                mode = recv_stop(&mut self.recv_stop) => {
+                    // The system was dropped without shutting down.
+                    let mode = mode.unwrap_or(StopMode::Immediate);
+                    self.context.request_stop(mode, TerminationReason::Shutdown);
                 }
-                received = self.recv_tell.recv() => {
-                    if let Some(message) = received {
-                        let mut guard = self.handler.lock().await;
-                        guard.handle(&mut self.context, message).await;
-                    }
+                envelope = self.recv_tell.recv() => {
+                    running = self.deliver(envelope).await;
                 }
-                received = self.recv_broadcast.recv() => {
-                    if let Ok(message) = received {
-                        let mut guard = self.handler.lock().await;
-                        guard.handle(&mut self.context, message).await;
+                received = recv_broadcast(&mut self.subscriptions) => {
+                    running = self.received(received).await;
+                }
+                received = recv_broadcast(&mut self.subscribed) => {
+                    running = self.received(received).await;
+                }
+            }
+        }
+
+        // Timers and streams must not deliver anything while the actor is
+        // stopping.
+        self.context.cancel_tasks();
+        let mode = self.context.stop_requested().unwrap_or(StopMode::Immediate);
+        if running && mode == StopMode::Drain {
+            running = self.drain().await;
+        }
+
+        // A panicked actor may be in an inconsistent state, so it does not
+        // get to run its stop hooks. Nor is it restarted for panicking in
+        // one, since it is stopping anyway.
+        if running {
+            let stopping = AssertUnwindSafe(self.actor.stopping(&mut self.context))
+                .catch_unwind()
+                .await;
+            running = self.survived(stopping.is_ok());
+        }
+        let reason = self
+            .context
+            .stop_reason()
+            .unwrap_or(TerminationReason::Stopped);
+        self.context.stop_children(mode, reason).await;
+        if running {
+            let stopped = AssertUnwindSafe(self.actor.stopped()).catch_unwind().await;
+            running = self.survived(stopped.is_ok());
+        }
+        running
+    }
+
+    /// Records a panic in a stop hook, passing on whether the hook returned.
+    fn survived(&mut self, returned: bool) -> bool {
+        if !returned {
+            self.failure = TerminationReason::Panicked;
+        }
+        returned
+    }
+
+    /// Stops accepting direct messages, then handles every message already
+    /// received. Returns `false` if the actor failed and could not be
+    /// restarted.
+    async fn drain(&mut self) -> bool {
+        self.recv_tell.close_to_messages();
+        loop {
+            let received = match self.recv_tell.try_recv() {
+                Some(envelope) => Received::Message(envelope),
+                None => match try_recv_broadcast(&mut self.subscriptions)
+                    .or_else(|| try_recv_broadcast(&mut self.subscribed))
+                {
+                    Some(received) => received,
+                    None => return true,
+                },
+            };
+            if !self.received(received).await {
+                return false;
+            }
+        }
+    }
+
+    /// Runs the `started` hook, returning `false` if the actor failed and
+    /// could not be restarted.
+    async fn started(&mut self) -> bool {
+        let started = AssertUnwindSafe(self.actor.started(&mut self.context))
+            .catch_unwind()
+            .await;
+        match started {
+            Ok(()) => true,
+            Err(_) => self.restart(TerminationReason::Panicked).await,
+        }
+    }
+
+    /// Delivers a message, returning `false` if the actor failed and could
+    /// not be restarted.
+    async fn deliver(&mut self, mut envelope: Box<dyn Envelope<A>>) -> bool {
+        self.context.set_envelope(envelope.take_metadata());
+        let delivered = AssertUnwindSafe(envelope.deliver(&mut self.actor, &mut self.context))
+            .catch_unwind()
+            .await;
+        self.context.set_envelope(None);
+        match delivered {
+            Ok(()) => true,
+            Err(_) => self.restart(TerminationReason::Panicked).await,
+        }
+    }
+
+    /// Handles what a subscription produced, returning `false` if the actor
+    /// failed and could not be restarted.
+    async fn received(&mut self, received: Received<A>) -> bool {
+        match received {
+            Received::Message(envelope) => self.deliver(envelope).await,
+            Received::Lagged(lagged) => self.lagged(lagged).await,
+        }
+    }
+
+    /// Applies the lag policy, returning `false` if the actor failed and
+    /// could not be restarted.
+    async fn lagged(&mut self, lagged: Lagged) -> bool {
+        self.metrics.record_lag(lagged.skipped);
+        match self.lag_policy {
+            LagPolicy::Count => true,
+            LagPolicy::Notify => self.deliver(Box::new(LaggedEnvelope::new(lagged))).await,
+            LagPolicy::Fail => self.restart(TerminationReason::Lagged).await,
+        }
+    }
+
+    /// Hands a failed actor to its supervisor, returning `false` if it was
+    /// not restarted.
+    async fn restart(&mut self, failure: TerminationReason) -> bool {
+        self.failure = failure;
+        let mut failed_starts = 0;
+        loop {
+            // The failed instance's children, timers, streams and
+            // subscriptions must not outlive it, or its replacement would
+            // start its own alongside them.
+            self.context.cancel_tasks();
+            self.subscribed.clear();
+            self.context.take_subscriptions();
+            self.context
+                .stop_children(StopMode::Immediate, TerminationReason::Stopped)
+                .await;
+            let delay = match self.supervisor.restart_delay() {
+                Some(delay) => delay,
+                None => {
+                    if self.supervisor.is_supervised() {
+                        self.failure = TerminationReason::SupervisorGaveUp;
                     }
+                    return false;
                 }
+            };
+            if !self.back_off(delay).await {
+                return false;
+            }
+            self.actor = self.supervisor.rebuild();
+            let started = AssertUnwindSafe(self.actor.started(&mut self.context))
+                .catch_unwind()
+                .await;
+            if started.is_ok() {
+                return true;
+            }
+            self.failure = TerminationReason::Panicked;
+            failed_starts += 1;
+            if failed_starts >= MAX_FAILED_STARTS {
+                self.failure = TerminationReason::SupervisorGaveUp;
+                return false;
+            }
+        }
+    }
+
+    /// Waits before a restart, returning `false` if the actor was stopped or
+    /// the system started shutting down meanwhile.
+    ///
+    /// Yields even without a delay, so that an actor whose replacements keep
+    /// failing does not hold on to its worker thread.
+    async fn back_off(&mut self, delay: Duration) -> bool {
+        let shutdown = self.context.system().shutdown_signal();
+        let wait = async {
+            if delay.is_zero() {
+                yield_now().await;
+            } else {
+                sleep(delay).await;
+            }
+        };
+        select! {
+            biased;
+            mode = recv_stop(&mut self.recv_stop) => {
+                let mode = mode.unwrap_or(StopMode::Immediate);
+                self.context.request_stop(mode, TerminationReason::Shutdown);
+                false
+            }
+            () = signalled(shutdown) => false,
+            () = wait => true,
+        }
+    }
+}
+
+/// Completes once `signal` turns `true`, e.g. when the system aborts its
+/// remaining actors or starts shutting down.
+async fn signalled(mut signal: watch::Receiver<bool>) {
+    if signal.wait_for(|signalled| *signalled).await.is_err() {
+        // The system is gone, so it can no longer signal anything.
+        pending::<()>().await;
+    }
+}
+
+/// Takes a message from the first subscribed broadcast channel that has one
+/// ready.
+fn try_recv_broadcast<A>(subscriptions: &mut [Box<dyn Subscription<A>>]) -> Option<Received<A>> {
+    subscriptions
+        .iter_mut()
+        .find_map(|subscription| subscription.try_recv())
+}
+
+/// Waits for the system to stop the actor, if it is a top level actor.
+async fn recv_stop(recv_stop: &mut Option<mpsc::UnboundedReceiver<StopMode>>) -> Option<StopMode> {
+    match recv_stop {
+        Some(recv_stop) => recv_stop.recv().await,
+        None => pending().await,
+    }
+}
+
+/// Returns a stop the system has already sent, if any.
+fn try_recv_stop(recv_stop: &mut Option<mpsc::UnboundedReceiver<StopMode>>) -> Option<StopMode> {
+    match recv_stop.as_mut()?.try_recv() {
+        Ok(mode) => Some(mode),
+        Err(TryRecvError::Empty) => None,
+        // The system was dropped without shutting down.
+        Err(TryRecvError::Disconnected) => Some(StopMode::Immediate),
+    }
+}
+
+/// Waits for the next message from any subscribed broadcast channel.
+async fn recv_broadcast<A>(subscriptions: &mut Vec<Box<dyn Subscription<A>>>) -> Received<A> {
+    loop {
+        if subscriptions.is_empty() {
+            return pending().await;
+        }
+
+        let (received, index, remaining) = select_all(
+            subscriptions
+                .iter_mut()
+                .map(|subscription| subscription.recv()),
+        )
+        .await;
+        drop(remaining);
+        match received {
+            Some(received) => return received,
+            None => {
+                subscriptions.swap_remove(index);
             }
         }
     }
@@ -3,26 +3,27 @@ use ::std::fmt::Debug;
 pub trait Message: 'static + Clone + Debug + Send {}
 impl<M> Message for M where M: 'static + Clone + Debug + Send {}
 
-#[derive(Clone, Debug)]
-pub enum SystemMessage {
-    Shutdown,
-}
-
 pub(crate) mod detail {
     use super::*;
-    use crate::{channel::BroadcastChannel, error::YaafInternalError};
-    use ::async_trait::async_trait;
-    use ::std::{any::TypeId, collections::HashMap};
-    use ::tokio::sync::broadcast::{channel, Sender};
+    use crate::{
+        channel::{channel, BroadcastChannel, ChannelCapacities},
+        error::YaafInternalError,
+    };
+    use ::std::{any::TypeId, collections::HashMap, marker::PhantomData};
 
-    #[async_trait]
+    /// A type-level list of message types: either a tuple of at most ten, or
+    /// any number nested in [`Cons`], ending with `()`.
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` is not a list of message types",
+        note = "tuples may hold at most 10 message types, nest longer lists in `yaaf::Cons`"
+    )]
     pub trait MessageList {
-        async fn setup_channels(
-            system_channel: Sender<SystemMessage>,
+        fn setup_channels(
+            capacities: &ChannelCapacities,
             broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
         ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError>;
-        async fn setup_channels_impl(
-            system_channel: Sender<SystemMessage>,
+        fn setup_channels_impl(
+            capacities: &ChannelCapacities,
             broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
             result: HashMap<TypeId, Box<dyn BroadcastChannel>>,
         ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError>;
@@ -30,47 +31,45 @@ pub(crate) mod detail {
 
     macro_rules! impl_message_list {
         ( $head:ident, $( $tail:ident, )* ) => {
-            #[async_trait]
             impl<$head, $( $tail ),*> MessageList for ($head, $( $tail ),*)
             where
                 $head: Message,
                 $( $tail: Message),*
             {
-                async fn setup_channels(
-                    system_channel: Sender<SystemMessage>,
+                fn setup_channels(
+                    capacities: &ChannelCapacities,
                     broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
                 ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError> {
-                    Self::setup_channels_impl(system_channel, broadcast_channels, HashMap::new()).await
+                    Self::setup_channels_impl(capacities, broadcast_channels, HashMap::new())
                 }
 
-                async fn setup_channels_impl(
-                    system_channel: Sender<SystemMessage>,
+                fn setup_channels_impl(
+                    capacities: &ChannelCapacities,
                     broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
                     mut result: HashMap<TypeId, Box<dyn BroadcastChannel>>,
                 ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError> {
                     let type_id = TypeId::of::<$head>();
-                    let r = broadcast_channels.entry(type_id).or_insert(Box::new(
-                        channel::<$head>(1000).0
+                    let r = broadcast_channels.entry(type_id).or_insert_with(|| Box::new(
+                        channel::<$head>(capacities.of::<$head>())
                     ));
                     result.insert(type_id, r.clone());
-                    <($( $tail, )*) as MessageList>::setup_channels_impl(system_channel, broadcast_channels, result).await
+                    <($( $tail, )*) as MessageList>::setup_channels_impl(capacities, broadcast_channels, result)
                 }
             }
 
             impl_message_list!($( $tail, )*);
         };
         () => {
-            #[async_trait]
             impl MessageList for () {
-                async fn setup_channels(
-                    system_channel: Sender<SystemMessage>,
+                fn setup_channels(
+                    capacities: &ChannelCapacities,
                     broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
                 ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError> {
-                    Self::setup_channels_impl(system_channel, broadcast_channels, HashMap::new()).await
+                    Self::setup_channels_impl(capacities, broadcast_channels, HashMap::new())
                 }
 
-                async fn setup_channels_impl(
-                    _system_channel: Sender<SystemMessage>,
+                fn setup_channels_impl(
+                    _capacities: &ChannelCapacities,
                     _broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
                     result: HashMap<TypeId, Box<dyn BroadcastChannel>>,
                 ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError> {
@@ -81,4 +80,34 @@ pub(crate) mod detail {
     }
 
     impl_message_list!(M10, M9, M8, M7, M6, M5, M4, M3, M2, M1,);
+
+    /// The list of `H` followed by the messages in `T`, which the derive
+    /// macros generate so that an actor may name any number of messages.
+    pub struct Cons<H, T>(PhantomData<fn() -> (H, T)>);
+
+    impl<H, T> MessageList for Cons<H, T>
+    where
+        H: Message,
+        T: MessageList,
+    {
+        fn setup_channels(
+            capacities: &ChannelCapacities,
+            broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
+        ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError> {
+            Self::setup_channels_impl(capacities, broadcast_channels, HashMap::new())
+        }
+
+        fn setup_channels_impl(
+            capacities: &ChannelCapacities,
+            broadcast_channels: &mut HashMap<TypeId, Box<dyn BroadcastChannel>>,
+            mut result: HashMap<TypeId, Box<dyn BroadcastChannel>>,
+        ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError> {
+            let type_id = TypeId::of::<H>();
+            let r = broadcast_channels
+                .entry(type_id)
+                .or_insert_with(|| Box::new(channel::<H>(capacities.of::<H>())));
+            result.insert(type_id, r.clone());
+            T::setup_channels_impl(capacities, broadcast_channels, result)
+        }
+    }
 }
@@ -0,0 +1,122 @@
+use crate::actor::ActorId;
+use ::std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use ::tokio::{task_local, time::Instant};
+
+/// Headers attached to a message, which are passed on to every message sent
+/// while handling it.
+pub type Headers = BTreeMap<String, String>;
+
+/// Identifies a chain of messages. A message sent while an actor handles
+/// another shares its correlation id, and any other message starts a new
+/// chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CorrelationId(u64);
+
+impl CorrelationId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        CorrelationId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// How a message reached the actor handling it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// Told or asked directly, including by the actor's own timers and
+    /// streams.
+    Direct,
+    /// Published to every actor that handles it.
+    Broadcast,
+}
+
+/// The envelope of a message, available through
+/// [`Context::envelope`](crate::prelude::Context::envelope) while it is
+/// handled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    /// The actor that sent the message, or `None` if it was sent from outside
+    /// any actor, e.g. by a source.
+    pub sender: Option<ActorId>,
+    pub origin: Origin,
+    pub correlation_id: CorrelationId,
+    /// When the message was told, asked or published.
+    pub enqueued_at: Instant,
+    pub headers: Headers,
+}
+
+impl Metadata {
+    /// The metadata of a message sent now, from the actor whose task this
+    /// runs in, if any, with `headers` on top of those it inherits.
+    pub(crate) fn new(origin: Origin, headers: Headers) -> Self {
+        let (sender, correlation_id, mut inherited) = CAUSE
+            .try_with(|cause| {
+                let cause = cause.borrow();
+                (
+                    Some(cause.sender),
+                    cause.correlation_id,
+                    cause.headers.clone(),
+                )
+            })
+            .unwrap_or((None, None, Headers::new()));
+        inherited.extend(headers);
+        Metadata {
+            sender,
+            origin,
+            correlation_id: correlation_id.unwrap_or_else(CorrelationId::next),
+            enqueued_at: Instant::now(),
+            headers: inherited,
+        }
+    }
+}
+
+/// What messages sent from an actor's task inherit.
+struct Cause {
+    sender: ActorId,
+    correlation_id: Option<CorrelationId>,
+    headers: Headers,
+}
+
+task_local! {
+    static CAUSE: RefCell<Cause>;
+}
+
+/// Runs `future` as the task of the actor `sender`, so that the messages it
+/// sends are from that actor.
+pub(crate) async fn sent_by<F: Future>(sender: ActorId, future: F) -> F::Output {
+    let cause = Cause {
+        sender,
+        correlation_id: None,
+        headers: Headers::new(),
+    };
+    CAUSE.scope(RefCell::new(cause), future).await
+}
+
+/// Makes `future` send from the actor whose task this runs in, if any, e.g.
+/// for its timers and streams. What it sends starts new chains.
+pub(crate) fn inherit_sender<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let sender = CAUSE.try_with(|cause| cause.borrow().sender).ok();
+    async move {
+        match sender {
+            Some(sender) => sent_by(sender, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Makes the messages sent from this actor's task continue the chain of the
+/// message it is handling, or start new chains if it is not handling one.
+pub(crate) fn handling(metadata: Option<&Metadata>) {
+    // Only a mailbox's task records what it is handling.
+    let _ = CAUSE.try_with(|cause| {
+        let mut cause = cause.borrow_mut();
+        cause.correlation_id = metadata.map(|metadata| metadata.correlation_id);
+        cause.headers = metadata
+            .map(|metadata| metadata.headers.clone())
+            .unwrap_or_default();
+    });
+}
@@ -0,0 +1,32 @@
+use ::std::sync::atomic::{AtomicU64, Ordering};
+
+/// A snapshot of the counters kept for an actor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ActorMetrics {
+    /// How many times the actor fell behind a broadcast channel.
+    pub lag_events: u64,
+    /// How many published messages the actor missed as a result.
+    pub skipped_messages: u64,
+}
+
+/// The live counters behind [`ActorMetrics`], shared by an actor's mailbox
+/// and its addresses.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    lag_events: AtomicU64,
+    skipped_messages: AtomicU64,
+}
+
+impl MetricsRecorder {
+    pub(crate) fn record_lag(&self, skipped: u64) {
+        self.lag_events.fetch_add(1, Ordering::Relaxed);
+        self.skipped_messages.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> ActorMetrics {
+        ActorMetrics {
+            lag_events: self.lag_events.load(Ordering::Relaxed),
+            skipped_messages: self.skipped_messages.load(Ordering::Relaxed),
+        }
+    }
+}
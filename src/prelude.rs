@@ -1,11 +1,13 @@
 //! Useful imports for most use-cases.
 
 pub use crate::{
-    actor::{Actor, Tell},
+    actor::{Actor, Ask, Tell},
     context::{Context, Publish},
+    dispatch::Dispatch,
     handler::Handler,
+    responder::Responder,
     source::Source,
     system::System,
 };
 pub use ::async_trait::async_trait;
-pub use ::yaaf_macros::{Actor, Source};
+pub use ::yaaf_macros::{actor, Actor, Dispatch, Source};
@@ -9,4 +9,24 @@ use crate::message::Message;
 ///
 /// [`Actor`]: ::yaaf_macros::Actor
 /// [`Source`]: ::yaaf_macros::Source
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not declare `#[publish({M})]`",
+    label = "`{M}` is not published by `{Self}`",
+    note = "add `{M}` to the `publish` attribute of `{Self}`"
+)]
 pub trait Publisher<M: Message> {}
+
+pub(crate) mod detail {
+    use super::*;
+
+    /// `A: Publisher<M>`, with `M` first so that it is only checked once `M`
+    /// is known, and reported with `M` rather than `_`.
+    pub trait Publishable<A> {}
+
+    impl<A, M> Publishable<A> for M
+    where
+        A: Publisher<M>,
+        M: Message,
+    {
+    }
+}
@@ -0,0 +1,310 @@
+use crate::envelope::Envelope;
+use ::std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex},
+};
+use ::tokio::sync::{Notify, Semaphore, TryAcquireError};
+
+/// What a bounded mailbox does with a message that arrives while it is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// [`Tell::tell_wait`] and [`Ask::ask`] wait for room, while
+    /// [`Tell::tell`] fails with [`AddressError::MailboxFull`].
+    ///
+    /// [`AddressError::MailboxFull`]: crate::error::AddressError::MailboxFull
+    /// [`Ask::ask`]: crate::Ask::ask
+    /// [`Tell::tell`]: crate::Tell::tell
+    /// [`Tell::tell_wait`]: crate::Tell::tell_wait
+    Block,
+    /// The message is rejected with [`AddressError::MailboxFull`].
+    ///
+    /// [`AddressError::MailboxFull`]: crate::error::AddressError::MailboxFull
+    Fail,
+    /// The oldest queued message is dropped to make room.
+    DropOldest,
+    /// The new message is dropped.
+    DropNewest,
+}
+
+/// Configures the queue of direct messages for an actor.
+///
+/// Published messages are buffered by their broadcast channel instead, and
+/// are not affected by this configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MailboxConfig {
+    /// The maximum number of queued messages, or `None` for no limit.
+    pub capacity: Option<usize>,
+    /// What to do with messages that arrive while the mailbox is full.
+    pub overflow: OverflowPolicy,
+}
+
+impl MailboxConfig {
+    pub fn unbounded() -> Self {
+        MailboxConfig {
+            capacity: None,
+            overflow: OverflowPolicy::Block,
+        }
+    }
+
+    /// A mailbox that holds at most `capacity` messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn bounded(capacity: usize, overflow: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "mailbox capacity must be positive");
+        MailboxConfig {
+            capacity: Some(capacity),
+            overflow,
+        }
+    }
+}
+
+impl Default for MailboxConfig {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum QueueError {
+    Closed,
+    Full,
+}
+
+/// How an envelope was admitted to the queue.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Admission {
+    /// The envelope holds one of the slots of a bounded mailbox.
+    Slot,
+    /// The mailbox is unbounded.
+    Unbounded,
+    /// The envelope was forced in by the framework.
+    Forced,
+}
+
+struct Queue<A> {
+    /// Set once the mailbox stops accepting messages, other than forced ones.
+    closed_to_messages: bool,
+    closed: bool,
+    envelopes: VecDeque<(Box<dyn Envelope<A>>, Admission)>,
+}
+
+struct Shared<A> {
+    overflow: OverflowPolicy,
+    queue: Mutex<Queue<A>>,
+    ready: Notify,
+    slots: Option<Semaphore>,
+}
+
+pub(crate) fn queue<A>(config: MailboxConfig) -> (MailboxSender<A>, MailboxReceiver<A>) {
+    let shared = Arc::new(Shared {
+        overflow: config.overflow,
+        queue: Mutex::new(Queue {
+            closed_to_messages: false,
+            closed: false,
+            envelopes: VecDeque::new(),
+        }),
+        ready: Notify::new(),
+        slots: config.capacity.map(Semaphore::new),
+    });
+    (MailboxSender(shared.clone()), MailboxReceiver(shared))
+}
+
+pub(crate) struct MailboxSender<A>(Arc<Shared<A>>);
+
+impl<A> Clone for MailboxSender<A> {
+    fn clone(&self) -> Self {
+        MailboxSender(self.0.clone())
+    }
+}
+
+impl<A> fmt::Debug for MailboxSender<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MailboxSender")
+            .field("overflow", &self.0.overflow)
+            .finish()
+    }
+}
+
+impl<A> MailboxSender<A> {
+    /// Queues an envelope without waiting, applying the overflow policy if
+    /// the mailbox is full.
+    pub(crate) fn try_send(&self, envelope: Box<dyn Envelope<A>>) -> Result<(), QueueError> {
+        let slots = match &self.0.slots {
+            Some(slots) => slots,
+            None => return self.push(envelope, Admission::Unbounded),
+        };
+        match slots.try_acquire() {
+            Ok(permit) => {
+                permit.forget();
+                self.push(envelope, Admission::Slot)
+            }
+            Err(TryAcquireError::Closed) => Err(QueueError::Closed),
+            Err(TryAcquireError::NoPermits) => match self.0.overflow {
+                OverflowPolicy::Block | OverflowPolicy::Fail => Err(QueueError::Full),
+                OverflowPolicy::DropOldest => self.replace_oldest(envelope),
+                OverflowPolicy::DropNewest => Ok(()),
+            },
+        }
+    }
+
+    /// Queues an envelope, waiting for room if the mailbox is full and uses
+    /// [`OverflowPolicy::Block`].
+    pub(crate) async fn send(&self, envelope: Box<dyn Envelope<A>>) -> Result<(), QueueError> {
+        match (&self.0.slots, self.0.overflow) {
+            (Some(slots), OverflowPolicy::Block) => {
+                slots
+                    .acquire()
+                    .await
+                    .map_err(|_| QueueError::Closed)?
+                    .forget();
+                self.push(envelope, Admission::Slot)
+            }
+            _ => self.try_send(envelope),
+        }
+    }
+
+    /// Queues an envelope regardless of capacity, for messages the framework
+    /// must not lose.
+    pub(crate) fn force(&self, envelope: Box<dyn Envelope<A>>) -> Result<(), QueueError> {
+        self.push(envelope, Admission::Forced)
+    }
+
+    /// Stops accepting messages and queues a final envelope, failing if the
+    /// mailbox had already stopped accepting messages.
+    pub(crate) fn close_with(&self, envelope: Box<dyn Envelope<A>>) -> Result<(), QueueError> {
+        let rejected = {
+            let mut queue = self.0.lock();
+            if queue.closed || queue.closed_to_messages {
+                Some(envelope)
+            } else {
+                queue.closed_to_messages = true;
+                queue.envelopes.push_back((envelope, Admission::Forced));
+                None
+            }
+        };
+        if rejected.is_some() {
+            return Err(QueueError::Closed);
+        }
+        self.0.ready.notify_one();
+        Ok(())
+    }
+
+    /// Stops accepting messages, while still accepting envelopes forced in
+    /// by the framework.
+    pub(crate) fn close_to_messages(&self) {
+        self.0.lock().closed_to_messages = true;
+    }
+
+    fn push(&self, envelope: Box<dyn Envelope<A>>, admission: Admission) -> Result<(), QueueError> {
+        let rejected = {
+            let mut queue = self.0.lock();
+            if queue.closed || (queue.closed_to_messages && admission != Admission::Forced) {
+                Some(envelope)
+            } else {
+                queue.envelopes.push_back((envelope, admission));
+                None
+            }
+        };
+        if rejected.is_some() {
+            // A rejected envelope may hold a slot that must be given back.
+            if let (Admission::Slot, Some(slots)) = (admission, &self.0.slots) {
+                slots.add_permits(1);
+            }
+            return Err(QueueError::Closed);
+        }
+        self.0.ready.notify_one();
+        Ok(())
+    }
+
+    fn replace_oldest(&self, envelope: Box<dyn Envelope<A>>) -> Result<(), QueueError> {
+        let dropped = {
+            let mut queue = self.0.lock();
+            if queue.closed || queue.closed_to_messages {
+                return Err(QueueError::Closed);
+            }
+            let oldest = queue
+                .envelopes
+                .iter()
+                .position(|(_, admission)| *admission == Admission::Slot);
+            let dropped = oldest.and_then(|index| queue.envelopes.remove(index));
+            let admission = match dropped {
+                Some(_) => Admission::Slot,
+                None => Admission::Unbounded,
+            };
+            queue.envelopes.push_back((envelope, admission));
+            dropped
+        };
+        // Dropped outside the lock, since dropping an envelope can run
+        // arbitrary code.
+        drop(dropped);
+        self.0.ready.notify_one();
+        Ok(())
+    }
+}
+
+pub(crate) struct MailboxReceiver<A>(Arc<Shared<A>>);
+
+impl<A> MailboxReceiver<A> {
+    /// Waits for the next envelope. Only one task may receive at a time.
+    pub(crate) async fn recv(&mut self) -> Box<dyn Envelope<A>> {
+        loop {
+            if let Some(envelope) = self.try_recv() {
+                return envelope;
+            }
+            self.0.ready.notified().await;
+        }
+    }
+
+    /// Takes the next envelope, if there is one.
+    pub(crate) fn try_recv(&mut self) -> Option<Box<dyn Envelope<A>>> {
+        let (envelope, admission) = self.0.lock().envelopes.pop_front()?;
+        if let (Admission::Slot, Some(slots)) = (admission, &self.0.slots) {
+            slots.add_permits(1);
+        }
+        Some(envelope)
+    }
+
+    /// Stops accepting messages, while still accepting envelopes forced in
+    /// by the framework.
+    pub(crate) fn close_to_messages(&mut self) {
+        self.0.lock().closed_to_messages = true;
+    }
+
+    /// Returns how many queued envelopes are messages, rather than envelopes
+    /// forced in by the framework.
+    pub(crate) fn pending_messages(&self) -> usize {
+        self.0
+            .lock()
+            .envelopes
+            .iter()
+            .filter(|(_, admission)| *admission != Admission::Forced)
+            .count()
+    }
+}
+
+impl<A> Drop for MailboxReceiver<A> {
+    fn drop(&mut self) {
+        if let Some(slots) = &self.0.slots {
+            slots.close();
+        }
+        let envelopes = {
+            let mut queue = self.0.lock();
+            queue.closed = true;
+            ::std::mem::take(&mut queue.envelopes)
+        };
+        drop(envelopes);
+    }
+}
+
+impl<A> Shared<A> {
+    fn lock(&self) -> ::std::sync::MutexGuard<'_, Queue<A>> {
+        // The lock is never held across user code, so it cannot be poisoned
+        // by a panicking handler.
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    actor::{Actor, ActorAddress, ActorId, TerminationReason},
+    error::{RegistryError, YaafInternalError},
+};
+use ::std::{
+    any::{type_name, Any},
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use ::tokio::sync::watch;
+
+/// Actors registered under names, so that they can be found without passing
+/// their addresses around.
+///
+/// Entries are removed once their actor terminates.
+#[derive(Default)]
+pub(crate) struct Registry {
+    entries: Mutex<HashMap<Arc<str>, Entry>>,
+}
+
+struct Entry {
+    id: ActorId,
+    actor: &'static str,
+    address: Box<dyn Any + Send + Sync>,
+    termination: watch::Receiver<Option<TerminationReason>>,
+}
+
+impl Entry {
+    fn is_terminated(&self) -> bool {
+        self.termination.borrow().is_some()
+    }
+}
+
+impl Registry {
+    pub(crate) fn register<A: 'static + Actor>(
+        &self,
+        name: Arc<str>,
+        address: &ActorAddress<A>,
+    ) -> Result<(), RegistryError> {
+        let mut entries = self.lock()?;
+        let termination = address.termination();
+        // Checked under the lock, so that the actor's mailbox cannot have
+        // unregistered it already.
+        if termination.borrow().is_some() {
+            return Err(RegistryError::ActorStopped);
+        }
+        match entries.get(&name) {
+            Some(entry) if entry.id == address.id() => return Ok(()),
+            Some(entry) if !entry.is_terminated() => {
+                return Err(RegistryError::NameTaken {
+                    name: name.to_string(),
+                })
+            }
+            _ => {}
+        }
+        entries.insert(
+            name,
+            Entry {
+                id: address.id(),
+                actor: type_name::<A>(),
+                address: Box::new(address.clone()),
+                termination,
+            },
+        );
+        Ok(())
+    }
+
+    pub(crate) fn lookup<A: 'static + Actor>(
+        &self,
+        name: &str,
+    ) -> Result<ActorAddress<A>, RegistryError> {
+        let entries = self.lock()?;
+        let entry = entries
+            .get(name)
+            .filter(|entry| !entry.is_terminated())
+            .ok_or_else(|| RegistryError::NotRegistered {
+                name: name.to_string(),
+            })?;
+        entry
+            .address
+            .downcast_ref::<ActorAddress<A>>()
+            .cloned()
+            .ok_or_else(|| RegistryError::WrongType {
+                name: name.to_string(),
+                expected: type_name::<A>(),
+                actual: entry.actor,
+            })
+    }
+
+    pub(crate) fn is_registered(&self, name: &str) -> Result<bool, RegistryError> {
+        Ok(self
+            .lock()?
+            .get(name)
+            .is_some_and(|entry| !entry.is_terminated()))
+    }
+
+    /// Removes every name a terminated actor was registered under.
+    pub(crate) fn unregister(&self, id: ActorId) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, entry| entry.id != id);
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<Arc<str>, Entry>>, RegistryError> {
+        self.entries
+            .lock()
+            .map_err(|_| RegistryError::AccessFailure {
+                source: YaafInternalError::LockFailure,
+            })
+    }
+}
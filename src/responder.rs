@@ -0,0 +1,59 @@
+use crate::{actor::Actor, context::Context, message::Message};
+use ::async_trait::async_trait;
+
+/// Handles a message and produces a response for the asker.
+///
+/// Unlike [`Handler`], responders do not need to be listed in the [`Actor`]
+/// derive, since requests are only ever delivered directly via [`Ask`].
+///
+/// [`Actor`]: ::yaaf_macros::Actor
+/// [`Ask`]: crate::Ask
+/// [`Handler`]: crate::Handler
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not respond to `{M}`",
+    label = "`{Self}` has no `Responder<{M}>`",
+    note = "implement `Responder<{M}>` for `{Self}` to ask it `{M}`"
+)]
+#[async_trait]
+pub trait Responder<M: Message>: Actor + Send {
+    type Response: 'static + Send;
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, message: M) -> Self::Response;
+}
+
+pub(crate) mod detail {
+    use super::*;
+    use crate::{
+        envelope::{AskEnvelope, Envelope},
+        metadata::Metadata,
+    };
+    use ::tokio::sync::oneshot;
+
+    /// `A: Responder<M>`, with `M` first so that it is only checked once `M`
+    /// is known, like [`Tellable`](crate::handler::detail::Tellable).
+    pub trait Askable<A> {
+        type Response: 'static + Send;
+
+        fn envelope(
+            self,
+            metadata: Metadata,
+            reply: oneshot::Sender<Self::Response>,
+        ) -> Box<dyn Envelope<A>>;
+    }
+
+    impl<A, M> Askable<A> for M
+    where
+        A: Responder<M>,
+        M: Message,
+    {
+        type Response = A::Response;
+
+        fn envelope(
+            self,
+            metadata: Metadata,
+            reply: oneshot::Sender<Self::Response>,
+        ) -> Box<dyn Envelope<A>> {
+            Box::new(AskEnvelope::new(self, metadata, reply))
+        }
+    }
+}
@@ -2,6 +2,10 @@ use crate::{context::Context, message::detail::MessageList};
 use ::async_trait::async_trait;
 
 #[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a source",
+    note = "derive `Source` for `{Self}`"
+)]
 pub trait SourceMeta {
     type Publishes: MessageList;
 }
@@ -0,0 +1,104 @@
+use crate::{
+    context::Context,
+    error::ContextError,
+    message::Message,
+    publisher::Publisher,
+    source::{Source, SourceMeta},
+};
+use ::async_trait::async_trait;
+use ::futures::{Stream, StreamExt};
+use ::std::sync::atomic::{AtomicU64, Ordering};
+use ::tokio::task::AbortHandle;
+
+/// Identifies a stream added with
+/// [`Context::add_stream`](crate::prelude::Context::add_stream).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StreamId(u64);
+
+impl StreamId {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        StreamId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Delivered to [`Actor::stream_finished`] when a stream added with
+/// [`Context::add_stream`] ends.
+///
+/// [`Actor::stream_finished`]: crate::prelude::Actor::stream_finished
+/// [`Context::add_stream`]: crate::prelude::Context::add_stream
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamFinished {
+    pub stream: StreamId,
+}
+
+/// A stream feeding an actor, returned by
+/// [`Context::add_stream`](crate::prelude::Context::add_stream).
+///
+/// The stream is cancelled automatically when its actor stops. Dropping the
+/// handle does not cancel it.
+#[derive(Clone, Debug)]
+pub struct StreamHandle {
+    id: StreamId,
+    task: AbortHandle,
+}
+
+impl StreamHandle {
+    pub(crate) fn new(id: StreamId, task: AbortHandle) -> Self {
+        StreamHandle { id, task }
+    }
+
+    pub fn id(&self) -> StreamId {
+        self.id
+    }
+
+    /// Stops reading from the stream. A cancelled stream is not reported as
+    /// finished.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+}
+
+/// Publishes every item of a stream, for
+/// [`System::add_stream_source`](crate::prelude::System::add_stream_source).
+pub(crate) struct StreamSource<S> {
+    stream: S,
+}
+
+impl<S> StreamSource<S> {
+    pub(crate) fn new(stream: S) -> Self {
+        StreamSource { stream }
+    }
+}
+
+impl<S> SourceMeta for StreamSource<S>
+where
+    S: Stream,
+    S::Item: Message,
+{
+    type Publishes = (S::Item,);
+}
+
+impl<S> Publisher<S::Item> for StreamSource<S>
+where
+    S: Stream,
+    S::Item: Message,
+{
+}
+
+#[async_trait]
+impl<S> Source for StreamSource<S>
+where
+    S: 'static + Stream + Send,
+    S::Item: Message,
+{
+    async fn run(mut self, mut ctx: Context<Self>) {
+        let mut stream = Box::pin(self.stream.take_until(ctx.shutdown_requested()));
+        while let Some(message) = stream.next().await {
+            // Publishing fails harmlessly when nothing is subscribed yet.
+            if let Err(ContextError::ShuttingDown) = ctx.publish(message) {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use ::std::{collections::VecDeque, time::Duration};
+use ::tokio::time::Instant;
+
+/// How a supervised actor is treated when one of its handlers panics.
+///
+/// A restarted actor is rebuilt from its factory and keeps its mailbox, so
+/// existing [`ActorAddress`]es continue to work. Messages queued before the
+/// panic are delivered to the new instance. The children, timers and
+/// streams of the failed instance are stopped first, so that the new one
+/// starts without them.
+///
+/// Whatever the strategy, an actor is stopped with
+/// [`TerminationReason::SupervisorGaveUp`] once its `started` hook has
+/// panicked [`MAX_FAILED_STARTS`] times in a row after restarts.
+///
+/// [`ActorAddress`]: crate::ActorAddress
+/// [`TerminationReason::SupervisorGaveUp`]: crate::TerminationReason::SupervisorGaveUp
+#[derive(Clone, Copy, Debug, Default)]
+pub enum SupervisionStrategy {
+    /// Stop the actor the first time it panics.
+    #[default]
+    Stop,
+    /// Restart the actor immediately every time it panics.
+    Restart,
+    /// Restart the actor, doubling the delay after each consecutive panic.
+    ///
+    /// The delay starts at `min`, never exceeds `max`, and is reset once the
+    /// actor has run for longer than `max` without panicking. Shutting down
+    /// the system during a delay stops the actor without waiting for it.
+    RestartWithBackoff { min: Duration, max: Duration },
+    /// Restart the actor immediately, but stop it once it has been restarted
+    /// `max_restarts` times within `within`.
+    RestartWithLimit {
+        max_restarts: usize,
+        within: Duration,
+    },
+}
+
+/// How many replacements in a row may panic in their `started` hook before
+/// the supervisor gives up.
+pub const MAX_FAILED_STARTS: usize = 10;
+
+pub(crate) struct Supervisor<A> {
+    factory: Option<Box<dyn FnMut() -> A + Send>>,
+    strategy: SupervisionStrategy,
+    consecutive: u32,
+    last_restart: Option<Instant>,
+    restarts: VecDeque<Instant>,
+}
+
+impl<A> Supervisor<A> {
+    /// A supervisor for actors that cannot be rebuilt.
+    pub(crate) fn unsupervised() -> Self {
+        Supervisor {
+            factory: None,
+            strategy: SupervisionStrategy::Stop,
+            consecutive: 0,
+            last_restart: None,
+            restarts: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn new<F>(factory: F, strategy: SupervisionStrategy) -> Self
+    where
+        F: 'static + FnMut() -> A + Send,
+    {
+        Supervisor {
+            factory: Some(Box::new(factory)),
+            strategy,
+            consecutive: 0,
+            last_restart: None,
+            restarts: VecDeque::new(),
+        }
+    }
+
+    /// Whether the actor can be rebuilt at all.
+    pub(crate) fn is_supervised(&self) -> bool {
+        self.factory.is_some()
+    }
+
+    /// Returns how long to wait before restarting a panicked actor, or
+    /// `None` if the actor should stop instead.
+    pub(crate) fn restart_delay(&mut self) -> Option<Duration> {
+        self.factory.as_ref()?;
+        let now = Instant::now();
+
+        match self.strategy {
+            SupervisionStrategy::Stop => None,
+            SupervisionStrategy::Restart => Some(Duration::ZERO),
+            SupervisionStrategy::RestartWithBackoff { min, max } => {
+                if let Some(last) = self.last_restart {
+                    if now.duration_since(last) > max {
+                        self.consecutive = 0;
+                    }
+                }
+                let delay = min
+                    .checked_mul(1 << self.consecutive.min(31))
+                    .map_or(max, |delay| delay.min(max));
+                self.consecutive += 1;
+                self.last_restart = Some(now + delay);
+                Some(delay)
+            }
+            SupervisionStrategy::RestartWithLimit {
+                max_restarts,
+                within,
+            } => {
+                while let Some(first) = self.restarts.front() {
+                    if now.duration_since(*first) > within {
+                        self.restarts.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+                if self.restarts.len() >= max_restarts {
+                    return None;
+                }
+                self.restarts.push_back(now);
+                Some(Duration::ZERO)
+            }
+        }
+    }
+
+    /// Builds a replacement for a panicked actor, once
+    /// [`restart_delay`](Supervisor::restart_delay) has allowed it.
+    pub(crate) fn rebuild(&mut self) -> A {
+        let factory = self
+            .factory
+            .as_mut()
+            .expect("only supervised actors are restarted");
+        factory()
+    }
+}
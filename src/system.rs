@@ -1,21 +1,120 @@
 use crate::{
-    actor::{Actor, ActorAddress},
-    channel::BroadcastChannel,
-    context::Context,
-    error::SystemError,
-    message::{detail::MessageList, SystemMessage},
+    actor::{Actor, ActorAddress, ActorId, ActorMeta, TerminationReason},
+    channel::{self, stream, BroadcastChannel, ChannelCapacities, Published, Receiver, Sender},
+    context::{Context, StopMode},
+    error::{RegistryError, SystemError, YaafInternalError},
+    lag::Lagged,
+    mailbox::{ActorOptions, Mailbox, Owner},
+    message::{detail::MessageList, Message},
+    metadata::Headers,
+    registry::Registry,
     source::{Source, SourceMeta},
+    stream::StreamSource,
+};
+use ::futures::Stream;
+use ::std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
-use ::std::{any::TypeId, collections::HashMap};
 use ::tokio::{
     spawn,
-    sync::{broadcast, mpsc},
+    sync::{mpsc, watch},
+    task::JoinHandle,
+    time::{timeout_at, Instant},
 };
 
+/// The default capacity of every broadcast channel.
+const DEFAULT_CAPACITY: usize = 1000;
+
 pub struct System {
-    broadcast_channels: HashMap<TypeId, Box<dyn BroadcastChannel>>,
-    system_channel: broadcast::Sender<SystemMessage>,
-    done: Vec<mpsc::Receiver<()>>,
+    handle: SystemHandle,
+    /// Top level actors, in the order they were added.
+    actors: Vec<TopLevel>,
+    /// Pairs of `(dependent, dependency)` actors.
+    dependencies: Vec<(ActorId, ActorId)>,
+    sources: Vec<JoinHandle<()>>,
+}
+
+/// An actor added directly to a [`System`], as seen by the system.
+struct TopLevel {
+    id: ActorId,
+    stop: mpsc::UnboundedSender<StopMode>,
+    done: mpsc::Receiver<()>,
+}
+
+/// How [`System::shutdown_with`] treats messages that have not been handled
+/// yet.
+///
+/// Shutdown happens in phases. Sources are stopped first: they are told
+/// through [`Context::shutdown_requested`] and awaited, except in
+/// [`Immediate`](Self::Immediate) mode, where they are cancelled. Actors are
+/// then stopped one at a time, in reverse order of registration unless
+/// [`System::add_dependency`] says otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShutdownMode {
+    /// Stop accepting new tells and publishes, handle every message already
+    /// received, then stop.
+    Graceful,
+    /// Stop as soon as possible, dropping messages that have not been handled.
+    Immediate,
+    /// Shut down gracefully, but abort any source or actor still running
+    /// after the timeout. Aborted actors are interrupted even while handling
+    /// a message, and do not run their stop hooks.
+    GracefulWithTimeout(Duration),
+}
+
+/// Describes what happened during [`System::shutdown_with`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// How many messages each actor dropped without handling them. Actors
+    /// that dropped nothing are left out.
+    pub dropped: HashMap<ActorId, usize>,
+    /// Whether a graceful shutdown timed out and aborted the remaining
+    /// sources and actors.
+    pub aborted: bool,
+}
+
+impl ShutdownReport {
+    /// The number of messages dropped across every actor.
+    pub fn total_dropped(&self) -> usize {
+        self.dropped.values().sum()
+    }
+}
+
+/// Configures a [`System`] before it is created.
+///
+/// Each published message type gets a broadcast channel that buffers up to
+/// its capacity of messages for the slowest subscriber. Subscribers that fall
+/// further behind miss messages, as governed by their [`LagPolicy`].
+///
+/// [`LagPolicy`]: crate::LagPolicy
+#[derive(Clone, Debug)]
+pub struct SystemBuilder {
+    capacities: ChannelCapacities,
+}
+
+type TopicChannels = HashMap<TypeId, HashMap<Arc<str>, Box<dyn BroadcastChannel>>>;
+
+/// The parts of a [`System`] shared with the contexts of its actors, so that
+/// they can start actors of their own and find each other.
+///
+/// Returned by [`Context::system`].
+#[derive(Clone)]
+pub struct SystemHandle {
+    abort: Arc<watch::Sender<bool>>,
+    broadcast_channels: Arc<Mutex<HashMap<TypeId, Box<dyn BroadcastChannel>>>>,
+    /// Channels for messages published to a topic, by message type.
+    topic_channels: Arc<Mutex<TopicChannels>>,
+    capacities: Arc<ChannelCapacities>,
+    draining: Arc<AtomicBool>,
+    dropped: Arc<Mutex<HashMap<ActorId, usize>>>,
+    registry: Arc<Registry>,
+    shutdown: Arc<watch::Sender<bool>>,
 }
 
 impl Default for System {
@@ -26,59 +125,538 @@ impl Default for System {
 
 impl System {
     pub fn new() -> Self {
-        System {
-            broadcast_channels: HashMap::new(),
-            system_channel: broadcast::channel(1000).0,
-            done: Vec::new(),
-        }
-    }
-
-    pub async fn add_actor<A: Actor>(&mut self, actor: A) -> Result<ActorAddress<A>, SystemError> {
-        let publish_channels =
-            A::Publishes::setup_channels(self.system_channel.clone(), &mut self.broadcast_channels)
-                .await
-                .map_err(|source| SystemError::AddActorFailure { source })?;
-        let handle_channels =
-            A::Handles::setup_channels(self.system_channel.clone(), &mut self.broadcast_channels)
-                .await
-                .map_err(|source| SystemError::AddActorFailure { source })?;
-
-        let (direct_channels, done) = actor
-            .setup_mailboxes(
-                &handle_channels,
-                &publish_channels,
-                self.system_channel.clone(),
-            )
-            .await
+        Self::builder().build()
+    }
+
+    pub fn builder() -> SystemBuilder {
+        SystemBuilder::default()
+    }
+
+    pub async fn add_actor<A: 'static + Actor>(
+        &mut self,
+        actor: A,
+    ) -> Result<ActorAddress<A>, SystemError> {
+        self.add_actor_with(ActorOptions::new(actor)).await
+    }
+
+    /// Adds an actor started with `options`, e.g. under a name, with its own
+    /// mailbox configuration, or supervised.
+    ///
+    /// Fails with [`RegistryError::NameTaken`] if the actor is named, and a
+    /// running actor is already registered under that name.
+    pub async fn add_actor_with<A: 'static + Actor>(
+        &mut self,
+        options: ActorOptions<A>,
+    ) -> Result<ActorAddress<A>, SystemError> {
+        let name = options.registered_name();
+        self.handle
+            .reserve(name.as_deref())
+            .map_err(|source| SystemError::RegisterFailure { source })?;
+        let address = self.start_actor(options)?;
+        self.handle
+            .register_started(name, &address)
+            .map_err(|source| SystemError::RegisterFailure { source })?;
+        Ok(address)
+    }
+
+    /// Registers an actor under `name`, see [`SystemHandle::register`].
+    pub fn register<A: 'static + Actor>(
+        &self,
+        name: impl Into<Arc<str>>,
+        address: &ActorAddress<A>,
+    ) -> Result<(), RegistryError> {
+        self.handle.register(name, address)
+    }
+
+    /// Finds the actor registered under `name`, see [`SystemHandle::lookup`].
+    pub fn lookup<A: 'static + Actor>(&self, name: &str) -> Result<ActorAddress<A>, RegistryError> {
+        self.handle.lookup(name)
+    }
+
+    fn start_actor<A: 'static + Actor>(
+        &mut self,
+        options: ActorOptions<A>,
+    ) -> Result<ActorAddress<A>, SystemError> {
+        self.forget_stopped();
+        let (stop, recv_stop) = mpsc::unbounded_channel();
+        let (address, done) = self
+            .handle
+            .start_actor(options, Owner::System(recv_stop))
             .map_err(|source| SystemError::AddActorFailure { source })?;
-        self.done.extend(done);
+        self.actors.push(TopLevel {
+            id: address.id(),
+            stop,
+            done,
+        });
+        Ok(address)
+    }
 
-        Ok(ActorAddress::new(direct_channels))
+    /// Declares that `dependent` uses `dependency`, so that shutdown stops
+    /// `dependent` first.
+    ///
+    /// Both must be top level actors of this system, and dependencies may not
+    /// form a cycle.
+    pub fn add_dependency(
+        &mut self,
+        dependent: ActorId,
+        dependency: ActorId,
+    ) -> Result<(), SystemError> {
+        self.forget_stopped();
+        for id in [dependent, dependency] {
+            if !self.actors.iter().any(|actor| actor.id == id) {
+                return Err(SystemError::UnknownActor { id });
+            }
+        }
+        if dependent == dependency || self.depends_on(dependency, dependent) {
+            return Err(SystemError::DependencyCycle {
+                dependent,
+                dependency,
+            });
+        }
+        self.dependencies.push((dependent, dependency));
+        Ok(())
+    }
+
+    /// Forgets top level actors that have already stopped, e.g. through
+    /// [`ActorAddress::stop`], along with their dependencies.
+    fn forget_stopped(&mut self) {
+        self.actors.retain_mut(|actor| {
+            matches!(actor.done.try_recv(), Err(mpsc::error::TryRecvError::Empty))
+        });
+        let actors = &self.actors;
+        let running = |id: &ActorId| actors.iter().any(|actor| actor.id == *id);
+        self.dependencies
+            .retain(|(dependent, dependency)| running(dependent) && running(dependency));
+    }
+
+    /// Whether `dependent` depends on `dependency`, directly or not.
+    fn depends_on(&self, dependent: ActorId, dependency: ActorId) -> bool {
+        let mut pending = vec![dependent];
+        let mut seen = HashSet::new();
+        while let Some(id) = pending.pop() {
+            if id == dependency {
+                return true;
+            }
+            if seen.insert(id) {
+                pending.extend(
+                    self.dependencies
+                        .iter()
+                        .filter(|(from, _)| *from == id)
+                        .map(|(_, to)| *to),
+                );
+            }
+        }
+        false
+    }
+
+    /// Orders top level actors so that each is stopped before its
+    /// dependencies, and otherwise in reverse order of registration.
+    fn stop_order(&mut self) -> Vec<TopLevel> {
+        let mut remaining: Vec<TopLevel> = self.actors.drain(..).collect();
+        let mut order = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            // Dependencies are acyclic, so some remaining actor is not
+            // depended on by any other.
+            let next = remaining
+                .iter()
+                .rposition(|actor| {
+                    !remaining
+                        .iter()
+                        .any(|other| self.dependencies.contains(&(other.id, actor.id)))
+                })
+                .unwrap_or(remaining.len() - 1);
+            order.push(remaining.remove(next));
+        }
+        order
     }
 
     pub async fn add_source<S: 'static + Source + SourceMeta>(
         &mut self,
         source: S,
     ) -> Result<(), SystemError> {
-        let publish_channels =
-            S::Publishes::setup_channels(self.system_channel.clone(), &mut self.broadcast_channels)
-                .await
-                .map_err(|source| SystemError::AddSourceFailure { source })?;
-        let ctx = Context::new(publish_channels);
-        spawn(source.run(ctx));
+        let publish_channels = self
+            .handle
+            .setup_channels::<S::Publishes>()
+            .map_err(|source| SystemError::AddSourceFailure { source })?;
+        let ctx = Context::new(publish_channels, self.handle.clone());
+        self.sources.push(spawn(source.run(ctx)));
         Ok(())
     }
 
+    /// Publishes every item of `stream`, until it ends or the system starts
+    /// shutting down.
+    ///
+    /// This is a [`Source`] that publishes `S::Item`, without having to
+    /// write one.
+    pub async fn add_stream_source<S>(&mut self, stream: S) -> Result<(), SystemError>
+    where
+        S: 'static + Stream + Send,
+        S::Item: Message,
+    {
+        self.add_source(StreamSource::new(stream)).await
+    }
+
+    /// Returns a stream of every `M` published from now on, whether by an
+    /// actor, a source or [`publish`](Self::publish).
+    ///
+    /// Like an actor, the stream misses messages if it falls more than the
+    /// channel's capacity behind, and then yields a [`Lagged`] error before
+    /// carrying on. It ends once the system and all its actors are gone.
+    pub fn subscribe<M: Message>(
+        &self,
+    ) -> Result<impl Stream<Item = Result<M, Lagged>> + Send + 'static, SystemError> {
+        let channel = self
+            .handle
+            .channel::<M>()
+            .map_err(|source| SystemError::SubscribeFailure { source })?;
+        Ok(stream(channel.subscribe()))
+    }
+
+    /// Publishes a message from outside the system, to every actor that
+    /// handles it and every [`subscribe`](Self::subscribe)d stream.
+    ///
+    /// Fails like [`Publish::publish`], e.g. when nothing is subscribed to
+    /// `M`, or with [`SystemError::ShuttingDown`] once a graceful shutdown
+    /// has started.
+    ///
+    /// [`Publish::publish`]: crate::prelude::Publish::publish
+    pub fn publish<M: Message>(&self, message: M) -> Result<(), SystemError> {
+        if self.handle.is_draining() {
+            return Err(SystemError::ShuttingDown);
+        }
+        self.handle
+            .channel::<M>()
+            .and_then(|channel| Ok(channel.send(Published::new(message, Headers::new()))?))
+            .map_err(|source| SystemError::PublishFailure { source })?;
+        Ok(())
+    }
+
+    /// Publishes a message from outside the system to `topic`, see
+    /// [`Context::publish_to`].
+    pub fn publish_to<M: Message>(&self, topic: &str, message: M) -> Result<(), SystemError> {
+        if self.handle.is_draining() {
+            return Err(SystemError::ShuttingDown);
+        }
+        self.handle
+            .publish_to(topic, message)
+            .map_err(|source| SystemError::PublishFailure { source })
+    }
+
+    /// Stops every actor immediately. Equivalent to
+    /// [`shutdown_with`](Self::shutdown_with) in [`ShutdownMode::Immediate`].
     pub async fn shutdown(&mut self) -> Result<(), SystemError> {
-        self.system_channel
-            .send(SystemMessage::Shutdown)
-            .map_err(|source| SystemError::ShutdownError {
-                source: source.into(),
-            })?;
-        for r in &mut self.done {
+        self.shutdown_with(ShutdownMode::Immediate).await?;
+        Ok(())
+    }
+
+    pub async fn shutdown_with(
+        &mut self,
+        mode: ShutdownMode,
+    ) -> Result<ShutdownReport, SystemError> {
+        let (stop_mode, deadline) = match mode {
+            ShutdownMode::Graceful => (StopMode::Drain, None),
+            ShutdownMode::Immediate => (StopMode::Immediate, None),
+            ShutdownMode::GracefulWithTimeout(limit) => {
+                (StopMode::Drain, Some(Instant::now() + limit))
+            }
+        };
+
+        self.handle.shutdown.send_replace(true);
+        let mut aborted = !self.stop_sources(stop_mode, deadline).await;
+        if aborted {
+            // The sources used up the time, so the actors are not waited for.
+            self.handle.abort.send_replace(true);
+        }
+
+        if stop_mode == StopMode::Drain {
+            self.handle.draining.store(true, Ordering::SeqCst);
+        }
+        for mut actor in self.stop_order() {
+            if !aborted {
+                // The actor may already have stopped, e.g. after panicking.
+                let _ = actor.stop.send(stop_mode);
+                aborted = !within(deadline, actor.done.recv()).await;
+                if aborted {
+                    self.handle.abort.send_replace(true);
+                }
+            }
             // TODO: log this error
-            let _ = r.recv().await;
+            let _ = actor.done.recv().await;
+        }
+
+        let dropped = self
+            .handle
+            .dropped
+            .lock()
+            .map_err(|_| SystemError::ShutdownError {
+                source: YaafInternalError::LockFailure,
+            })?
+            .drain()
+            .collect();
+        Ok(ShutdownReport { dropped, aborted })
+    }
+
+    /// Stops every source, returning `false` if the deadline passed first and
+    /// the remaining sources were cancelled.
+    async fn stop_sources(&mut self, mode: StopMode, deadline: Option<Instant>) -> bool {
+        let mut in_time = true;
+        for mut source in self.sources.drain(..) {
+            if mode == StopMode::Immediate || !in_time {
+                source.abort();
+            }
+            if !within(deadline, &mut source).await {
+                in_time = false;
+                source.abort();
+                // The source was cancelled, which is expected.
+                let _ = source.await;
+            }
+        }
+        in_time
+    }
+}
+
+/// Runs `future` to completion, or until the deadline if there is one.
+/// Returns `false` if the deadline passed first.
+async fn within<F: ::std::future::Future>(deadline: Option<Instant>, future: F) -> bool {
+    match deadline {
+        Some(deadline) => timeout_at(deadline, future).await.is_ok(),
+        None => {
+            future.await;
+            true
+        }
+    }
+}
+
+impl Default for SystemBuilder {
+    fn default() -> Self {
+        SystemBuilder {
+            capacities: ChannelCapacities::new(DEFAULT_CAPACITY),
+        }
+    }
+}
+
+impl SystemBuilder {
+    /// Sets the capacity of broadcast channels for message types without a
+    /// capacity of their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn default_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "broadcast capacity must be positive");
+        self.capacities.set_default(capacity);
+        self
+    }
+
+    /// Sets the capacity of the broadcast channel for messages of type `M`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn capacity<M: Message>(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "broadcast capacity must be positive");
+        self.capacities.set::<M>(capacity);
+        self
+    }
+
+    pub fn build(self) -> System {
+        System {
+            handle: SystemHandle {
+                abort: Arc::new(watch::channel(false).0),
+                broadcast_channels: Arc::new(Mutex::new(HashMap::new())),
+                capacities: Arc::new(self.capacities),
+                draining: Arc::new(AtomicBool::new(false)),
+                dropped: Arc::new(Mutex::new(HashMap::new())),
+                registry: Arc::new(Registry::default()),
+                shutdown: Arc::new(watch::channel(false).0),
+                topic_channels: Arc::new(Mutex::new(HashMap::new())),
+            },
+            actors: Vec::new(),
+            dependencies: Vec::new(),
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl SystemHandle {
+    /// Registers an actor under `name`, so that it can be found with
+    /// [`lookup`](Self::lookup) until it terminates.
+    ///
+    /// Registering an actor again under the same name does nothing. Fails
+    /// with [`RegistryError::NameTaken`] if another running actor has the
+    /// name, or [`RegistryError::ActorStopped`] if this one has terminated.
+    pub fn register<A: 'static + Actor>(
+        &self,
+        name: impl Into<Arc<str>>,
+        address: &ActorAddress<A>,
+    ) -> Result<(), RegistryError> {
+        self.registry.register(name.into(), address)
+    }
+
+    /// Fails with [`RegistryError::NameTaken`] if a running actor is already
+    /// registered under `name`, before another one is started under it.
+    pub(crate) fn reserve(&self, name: Option<&str>) -> Result<(), RegistryError> {
+        match name {
+            Some(name) if self.registry.is_registered(name)? => Err(RegistryError::NameTaken {
+                name: name.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Registers an actor that has just been started under `name`, if any,
+    /// stopping it again if the registration fails.
+    pub(crate) fn register_started<A: 'static + Actor>(
+        &self,
+        name: Option<Arc<str>>,
+        address: &ActorAddress<A>,
+    ) -> Result<(), RegistryError> {
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        if let Err(error) = self.register(name, address) {
+            // Another actor took the name while this one was starting.
+            address.request_stop(StopMode::Immediate, TerminationReason::Stopped);
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Finds the running actor registered under `name`.
+    ///
+    /// Fails with [`RegistryError::NotRegistered`] if there is none, or
+    /// [`RegistryError::WrongType`] if it is not an `A`.
+    pub fn lookup<A: 'static + Actor>(&self, name: &str) -> Result<ActorAddress<A>, RegistryError> {
+        self.registry.lookup(name)
+    }
+
+    /// Forgets the names of an actor that has terminated.
+    pub(crate) fn unregister(&self, id: ActorId) {
+        self.registry.unregister(id);
+    }
+
+    /// Whether a graceful shutdown has started, after which nothing may be
+    /// published.
+    pub(crate) fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Returns a receiver that changes to `true` when the system aborts its
+    /// remaining actors.
+    pub(crate) fn abort_signal(&self) -> watch::Receiver<bool> {
+        self.abort.subscribe()
+    }
+
+    /// Returns a receiver that changes to `true` when the system starts
+    /// shutting down.
+    pub(crate) fn shutdown_signal(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+
+    /// Records messages an actor dropped when it stopped.
+    pub(crate) fn record_dropped(&self, id: ActorId, count: usize) {
+        if let Ok(mut dropped) = self.dropped.lock() {
+            *dropped.entry(id).or_insert(0) += count;
         }
+    }
+
+    fn setup_channels<ML: MessageList>(
+        &self,
+    ) -> Result<HashMap<TypeId, Box<dyn BroadcastChannel>>, YaafInternalError> {
+        let mut broadcast_channels = self
+            .broadcast_channels
+            .lock()
+            .map_err(|_| YaafInternalError::LockFailure)?;
+        ML::setup_channels(&self.capacities, &mut broadcast_channels)
+    }
+
+    /// Returns the broadcast channel for `M`, creating it if need be.
+    pub(crate) fn channel<M: Message>(&self) -> Result<Sender<M>, YaafInternalError> {
+        self.setup_channels::<(M,)>()?
+            .get(&TypeId::of::<M>())
+            .and_then(|channel| channel.as_any().downcast_ref::<Sender<M>>())
+            .cloned()
+            .ok_or(YaafInternalError::ChannelLookupFailure)
+    }
+
+    /// Subscribes to the `M`s published to `topic`, creating its channel if
+    /// need be.
+    ///
+    /// Channels of other topics that have lost all their subscribers are
+    /// dropped on the way, so that topics such as per-customer ones do not
+    /// accumulate.
+    pub(crate) fn topic_receiver<M: Message>(
+        &self,
+        topic: &str,
+    ) -> Result<Receiver<M>, YaafInternalError> {
+        let mut topic_channels = self
+            .topic_channels
+            .lock()
+            .map_err(|_| YaafInternalError::LockFailure)?;
+        let channels = topic_channels.entry(TypeId::of::<M>()).or_default();
+        channels.retain(|_, channel| channel.receiver_count() > 0);
+        channels
+            .entry(topic.into())
+            .or_insert_with(|| Box::new(channel::channel::<M>(self.capacities.of::<M>())))
+            .as_any()
+            .downcast_ref::<Sender<M>>()
+            .map(Sender::subscribe)
+            .ok_or(YaafInternalError::ChannelLookupFailure)
+    }
+
+    /// Publishes `message` to the subscribers of `topic`.
+    pub(crate) fn publish_to<M: Message>(
+        &self,
+        topic: &str,
+        message: M,
+    ) -> Result<(), YaafInternalError> {
+        // Only topics with subscribers keep a channel. Without one, fail as
+        // sending to a channel nobody receives from does.
+        let channel = {
+            let mut topic_channels = self
+                .topic_channels
+                .lock()
+                .map_err(|_| YaafInternalError::LockFailure)?;
+            let channels = topic_channels
+                .get_mut(&TypeId::of::<M>())
+                .ok_or(YaafInternalError::SendFailure)?;
+            match channels
+                .get(topic)
+                .filter(|channel| channel.receiver_count() > 0)
+                .and_then(|channel| channel.as_any().downcast_ref::<Sender<M>>())
+                .cloned()
+            {
+                Some(channel) => channel,
+                None => {
+                    channels.remove(topic);
+                    if channels.is_empty() {
+                        topic_channels.remove(&TypeId::of::<M>());
+                    }
+                    return Err(YaafInternalError::SendFailure);
+                }
+            }
+        };
+        channel.send(Published::new(message, Headers::new()))?;
         Ok(())
     }
+
+    /// Starts an actor, returning its address and a receiver that is
+    /// signalled once it has stopped.
+    pub(crate) fn start_actor<A: 'static + Actor>(
+        &self,
+        options: ActorOptions<A>,
+        owner: Owner,
+    ) -> Result<(ActorAddress<A>, mpsc::Receiver<()>), YaafInternalError> {
+        let publish_channels = self.setup_channels::<A::Publishes>()?;
+        let subscriptions = <A as ActorMeta>::subscriptions(self)?;
+
+        Ok(Mailbox::start(
+            options,
+            subscriptions,
+            publish_channels,
+            self.clone(),
+            owner,
+        ))
+    }
 }
@@ -0,0 +1,28 @@
+use ::tokio::task::AbortHandle;
+
+/// A timer started through a [`Context`](crate::prelude::Context), such as
+/// [`Context::tell_self_after`](crate::prelude::Context::tell_self_after).
+///
+/// Timers are cancelled automatically when their actor stops. Dropping the
+/// handle does not cancel the timer.
+#[derive(Clone, Debug)]
+pub struct TimerHandle {
+    task: AbortHandle,
+}
+
+impl TimerHandle {
+    pub(crate) fn new(task: AbortHandle) -> Self {
+        TimerHandle { task }
+    }
+
+    /// Cancels the timer, so that it sends nothing more. Cancelling a timer
+    /// that has already fired or been cancelled does nothing.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Whether the timer has fired for the last time or been cancelled.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
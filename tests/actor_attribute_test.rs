@@ -0,0 +1,116 @@
+use ::std::fmt::Debug;
+use ::tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use ::yaaf::{error::SystemError, prelude::*};
+
+#[derive(Clone, Debug)]
+struct Ping(u32);
+
+#[derive(Clone, Debug)]
+struct Pong(u32);
+
+#[derive(Clone, Debug)]
+struct Reset;
+
+#[derive(Clone, Debug)]
+struct Count;
+
+struct Paddle {
+    hits: u32,
+}
+
+#[actor]
+#[publish(Pong)]
+#[mailbox(capacity = 8)]
+impl Paddle {
+    #[handler]
+    async fn ping(&mut self, ctx: &mut Context<Self>, message: Ping) {
+        self.hits += 1;
+        ctx.publish(Pong(message.0)).unwrap();
+    }
+
+    #[handler(direct_only)]
+    async fn reset(&mut self, _ctx: &mut Context<Self>, _message: Reset) {
+        self.hits = 0;
+    }
+}
+
+#[async_trait]
+impl Responder<Count> for Paddle {
+    type Response = u32;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Count) -> u32 {
+        self.hits
+    }
+}
+
+/// Forwards every message of one type.
+struct Forward<T: Clone + Debug + Send + 'static> {
+    sender: UnboundedSender<T>,
+}
+
+#[actor]
+impl<T: Clone + Debug + Send + 'static> Forward<T> {
+    #[handler]
+    async fn forward(&mut self, _ctx: &mut Context<Self>, message: T) {
+        self.sender.send(message).unwrap();
+    }
+}
+
+/// Reports when it starts, and every ping it handles.
+struct Greeter {
+    events: UnboundedSender<&'static str>,
+}
+
+#[actor(hooks)]
+impl Greeter {
+    #[handler]
+    async fn ping(&mut self, _ctx: &mut Context<Self>, _message: Ping) {
+        self.events.send("ping").unwrap();
+    }
+}
+
+#[async_trait]
+impl Actor for Greeter {
+    async fn started(&mut self, _ctx: &mut Context<Self>) {
+        self.events.send("started").unwrap();
+    }
+}
+
+#[tokio::test]
+async fn methods_become_handlers() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let paddle = system.add_actor(Paddle { hits: 0 }).await?;
+    let (send, mut pongs) = unbounded_channel();
+    system.add_actor(Forward::<Pong> { sender: send }).await?;
+
+    paddle.tell(Ping(1))?;
+    assert_eq!(1, pongs.recv().await.unwrap().0);
+    system.publish(Ping(2))?;
+    assert_eq!(2, pongs.recv().await.unwrap().0);
+    assert_eq!(2, paddle.ask(Count).await?);
+
+    // `Reset` is only handled when told.
+    assert!(matches!(
+        system.publish(Reset),
+        Err(SystemError::PublishFailure { .. })
+    ));
+    paddle.tell(Reset)?;
+    assert_eq!(0, paddle.ask(Count).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn hooks_are_kept() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut events) = unbounded_channel();
+    let greeter = system.add_actor(Greeter { events: send }).await?;
+
+    greeter.tell(Ping(1))?;
+    assert_eq!(Some("started"), events.recv().await);
+    assert_eq!(Some("ping"), events.recv().await);
+
+    system.shutdown().await?;
+    Ok(())
+}
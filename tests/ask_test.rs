@@ -0,0 +1,66 @@
+use ::yaaf::{error::AddressError, prelude::*};
+
+#[derive(Clone, Debug)]
+struct Increment(u32);
+
+#[derive(Clone, Debug)]
+struct Total;
+
+#[derive(Actor)]
+#[handle(Increment)]
+struct Counter {
+    count: u32,
+}
+
+#[async_trait]
+impl Handler<Increment> for Counter {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Increment) {
+        self.count += message.0;
+    }
+}
+
+#[async_trait]
+impl Responder<Increment> for Counter {
+    type Response = u32;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, message: Increment) -> u32 {
+        self.count += message.0;
+        self.count
+    }
+}
+
+#[async_trait]
+impl Responder<Total> for Counter {
+    type Response = u32;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Total) -> u32 {
+        self.count
+    }
+}
+
+#[tokio::test]
+async fn ask() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let address = system.add_actor(Counter { count: 0 }).await?;
+
+    assert_eq!(2, address.ask(Increment(2)).await?);
+    assert_eq!(5, address.ask(Increment(3)).await?);
+    assert_eq!(5, address.ask(Total).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn ask_after_shutdown() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let address = system.add_actor(Counter { count: 0 }).await?;
+    system.shutdown().await?;
+
+    let result = address.ask(Total).await;
+    assert!(matches!(result, Err(AddressError::ActorStopped)));
+
+    Ok(())
+}
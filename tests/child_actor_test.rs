@@ -0,0 +1,280 @@
+use ::std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use ::tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedSender},
+    Mutex,
+};
+use ::yaaf::{
+    prelude::*, ActorAddress, ActorOptions, ChildTerminated, SupervisionStrategy, TerminationReason,
+};
+
+#[derive(Clone, Debug)]
+struct SpawnWorker;
+
+#[derive(Clone, Debug)]
+struct SpawnNamedWorker;
+
+#[derive(Clone, Debug)]
+struct Work;
+
+#[derive(Clone, Debug)]
+struct Crash;
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Work, Crash)]
+struct Worker {
+    events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+#[async_trait]
+impl Actor for Worker {
+    async fn stopped(&mut self) {
+        self.events.lock().await.push("worker stopped");
+    }
+}
+
+#[async_trait]
+impl Handler<Work> for Worker {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Work) {
+        self.events.lock().await.push("worker worked");
+    }
+}
+
+#[async_trait]
+impl Handler<Crash> for Worker {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Crash) {
+        panic!("crashed on purpose");
+    }
+}
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Work)]
+struct Parent {
+    events: Arc<Mutex<Vec<&'static str>>>,
+    terminated: UnboundedSender<ChildTerminated>,
+}
+
+#[async_trait]
+impl Actor for Parent {
+    async fn stopped(&mut self) {
+        self.events.lock().await.push("parent stopped");
+    }
+
+    async fn child_terminated(&mut self, _ctx: &mut Context<Self>, terminated: ChildTerminated) {
+        self.terminated.send(terminated).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Work> for Parent {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Work) {}
+}
+
+#[async_trait]
+impl Responder<SpawnWorker> for Parent {
+    type Response = ActorAddress<Worker>;
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, _message: SpawnWorker) -> Self::Response {
+        ctx.spawn_child(Worker {
+            events: self.events.clone(),
+        })
+        .unwrap()
+    }
+}
+
+#[async_trait]
+impl Responder<SpawnNamedWorker> for Parent {
+    type Response = Option<ActorAddress<Worker>>;
+
+    async fn respond(
+        &mut self,
+        ctx: &mut Context<Self>,
+        _message: SpawnNamedWorker,
+    ) -> Self::Response {
+        let worker = Worker {
+            events: self.events.clone(),
+        };
+        ctx.spawn_child_with(ActorOptions::new(worker).name("worker"))
+            .ok()
+    }
+}
+
+#[tokio::test]
+async fn children_stop_with_parent() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (terminated, _recv_terminated) = unbounded_channel();
+    let parent = system
+        .add_actor(Parent {
+            events: events.clone(),
+            terminated,
+        })
+        .await?;
+
+    let worker = parent.ask(SpawnWorker).await?;
+    worker.tell(Work)?;
+
+    system.shutdown().await?;
+
+    assert_eq!(
+        vec!["worker worked", "worker stopped", "parent stopped"],
+        *events.lock().await
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn parent_is_told_about_child_failure() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (terminated, mut recv_terminated) = unbounded_channel();
+    let parent = system
+        .add_actor(Parent {
+            events: events.clone(),
+            terminated,
+        })
+        .await?;
+
+    let worker = parent.ask(SpawnWorker).await?;
+    worker.tell(Crash)?;
+
+    assert_eq!(
+        Some(ChildTerminated {
+            id: worker.id(),
+            reason: TerminationReason::Panicked,
+        }),
+        recv_terminated.recv().await
+    );
+
+    system.shutdown().await?;
+
+    assert_eq!(vec!["parent stopped"], *events.lock().await);
+    Ok(())
+}
+
+/// Counts how many instances are running.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Work)]
+struct Counted {
+    live: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Actor for Counted {
+    async fn started(&mut self, _ctx: &mut Context<Self>) {
+        self.live.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn stopped(&mut self) {
+        self.live.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Handler<Work> for Counted {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Work) {}
+}
+
+#[async_trait]
+impl Responder<Work> for Counted {
+    type Response = ();
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Work) {}
+}
+
+/// Spawns a child when it starts.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Crash)]
+struct Spawner {
+    live: Arc<AtomicUsize>,
+    child: Option<ActorAddress<Counted>>,
+}
+
+#[async_trait]
+impl Actor for Spawner {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        let child = Counted {
+            live: self.live.clone(),
+        };
+        self.child = Some(ctx.spawn_child(child).unwrap());
+    }
+}
+
+#[async_trait]
+impl Handler<Crash> for Spawner {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Crash) {
+        panic!("crashed on purpose");
+    }
+}
+
+#[async_trait]
+impl Responder<SpawnWorker> for Spawner {
+    type Response = ActorAddress<Counted>;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: SpawnWorker) -> Self::Response {
+        self.child.clone().unwrap()
+    }
+}
+
+#[tokio::test]
+async fn restart_stops_children() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let live = Arc::new(AtomicUsize::new(0));
+    let factory_live = live.clone();
+    let spawner = system
+        .add_actor_with(ActorOptions::supervised(
+            move || Spawner {
+                live: factory_live.clone(),
+                child: None,
+            },
+            SupervisionStrategy::Restart,
+        ))
+        .await?;
+
+    let first = spawner.ask(SpawnWorker).await?;
+    spawner.tell(Crash)?;
+    spawner.tell(Crash)?;
+    let last = spawner.ask(SpawnWorker).await?;
+    // The child has started once it answers.
+    last.ask(Work).await?;
+
+    assert_ne!(first, last);
+    assert_eq!(1, live.load(Ordering::SeqCst));
+    assert_eq!(TerminationReason::Stopped, first.closed().await);
+
+    system.shutdown().await?;
+    assert_eq!(0, live.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn named_children_are_registered() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (terminated, _recv_terminated) = unbounded_channel();
+    let parent = system
+        .add_actor(Parent {
+            events: events.clone(),
+            terminated,
+        })
+        .await?;
+
+    let worker = parent.ask(SpawnNamedWorker).await?.unwrap();
+    assert_eq!(Some("worker"), worker.name());
+    assert_eq!(worker, system.lookup::<Worker>("worker")?);
+    // The name stays taken while the first child runs.
+    assert!(parent.ask(SpawnNamedWorker).await?.is_none());
+
+    system.shutdown().await?;
+    Ok(())
+}
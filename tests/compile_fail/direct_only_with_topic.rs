@@ -0,0 +1,15 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Command;
+
+#[derive(Actor)]
+#[handle(Command, direct_only, topic = "admin")]
+struct MyActor;
+
+#[async_trait]
+impl Handler<Command> for MyActor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Command) {}
+}
+
+fn main() {}
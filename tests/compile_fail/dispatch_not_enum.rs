@@ -0,0 +1,8 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug, Dispatch)]
+struct Deposit {
+    amount: u64,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug, Dispatch)]
+enum Command {
+    Deposit { amount: u64 },
+}
+
+#[derive(Actor)]
+#[handle(Command, dispatch)]
+struct Account;
+
+fn main() {}
@@ -0,0 +1,15 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Order;
+
+#[derive(Actor)]
+#[handle(Order, region = "eu")]
+struct MyActor;
+
+#[async_trait]
+impl Handler<Order> for MyActor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Order) {}
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Ping;
+
+struct Paddle;
+
+#[actor]
+impl Paddle {
+    #[handler]
+    fn ping(&mut self, _ctx: &mut Context<Self>, _message: Ping) {}
+}
+
+fn main() {}
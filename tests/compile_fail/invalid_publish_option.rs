@@ -0,0 +1,10 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Order;
+
+#[derive(Source)]
+#[publish(Order, topic = "eu")]
+struct MySource;
+
+fn main() {}
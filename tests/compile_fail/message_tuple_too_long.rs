@@ -0,0 +1,12 @@
+use ::yaaf::SourceMeta;
+
+#[derive(Clone, Debug)]
+struct M;
+
+struct MySource;
+
+impl SourceMeta for MySource {
+    type Publishes = (M, M, M, M, M, M, M, M, M, M, M);
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Question;
+
+#[derive(Clone, Debug)]
+struct Other;
+
+#[derive(Actor)]
+struct MyActor;
+
+#[async_trait]
+impl Responder<Question> for MyActor {
+    type Response = ();
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Question) {}
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let address = system.add_actor(MyActor).await?;
+    address.ask(Other).await?;
+    Ok(())
+}
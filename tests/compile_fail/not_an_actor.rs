@@ -0,0 +1,7 @@
+use ::yaaf::prelude::*;
+
+struct MyActor;
+
+impl Actor for MyActor {}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Event;
+
+#[derive(Actor)]
+#[subscribe(Event)]
+struct MyActor;
+
+#[async_trait]
+impl Handler<Event> for MyActor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Event) {}
+}
+
+#[tokio::main]
+async fn main() {
+    let mut system = System::new();
+    let address = system.add_actor(MyActor).await.unwrap();
+    address.tell(Event).unwrap();
+}
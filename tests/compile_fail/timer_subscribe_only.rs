@@ -0,0 +1,24 @@
+use ::std::time::Duration;
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Event;
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[subscribe(Event)]
+struct MyActor;
+
+#[async_trait]
+impl Actor for MyActor {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_interval(Duration::from_secs(1), || Event);
+    }
+}
+
+#[async_trait]
+impl Handler<Event> for MyActor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Event) {}
+}
+
+fn main() {}
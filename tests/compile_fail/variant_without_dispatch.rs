@@ -0,0 +1,21 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug, Dispatch)]
+enum Command {
+    Deposit { amount: u64 },
+}
+
+struct Account {
+    balance: u64,
+}
+
+#[actor]
+#[handle(Command)]
+impl Account {
+    #[handler(variant = Deposit)]
+    async fn deposit(&mut self, _ctx: &mut Context<Self>, amount: u64) {
+        self.balance += amount;
+    }
+}
+
+fn main() {}
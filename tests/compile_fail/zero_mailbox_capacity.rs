@@ -0,0 +1,16 @@
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Order;
+
+#[derive(Actor)]
+#[handle(Order)]
+#[mailbox(capacity = 0)]
+struct MyActor;
+
+#[async_trait]
+impl Handler<Order> for MyActor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Order) {}
+}
+
+fn main() {}
@@ -0,0 +1,149 @@
+use ::std::fmt::Debug;
+use ::tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug, Dispatch)]
+enum Command {
+    Deposit { amount: u64 },
+    Withdraw(u64),
+    Transfer { amount: u64, to: &'static str },
+    Close,
+}
+
+#[derive(Clone, Debug, Dispatch)]
+enum Audit {
+    Reset,
+}
+
+#[derive(Clone, Debug)]
+struct Balance;
+
+#[derive(Clone, Debug)]
+struct Transfers;
+
+struct Account {
+    balance: u64,
+    open: bool,
+    transfers: Vec<(u64, &'static str)>,
+}
+
+#[actor]
+#[handle(Command, dispatch)]
+#[handle(Audit, dispatch, direct_only)]
+impl Account {
+    #[handler(Command, variant = Deposit)]
+    async fn deposit(&mut self, _ctx: &mut Context<Self>, amount: u64) {
+        self.balance += amount;
+    }
+
+    #[handler(Command, variant = Withdraw)]
+    async fn withdraw(&mut self, _ctx: &mut Context<Self>, amount: u64) {
+        self.balance = self.balance.saturating_sub(amount);
+    }
+
+    #[handler(Command, variant = Transfer)]
+    async fn transfer(&mut self, _ctx: &mut Context<Self>, amount: u64, to: &'static str) {
+        self.balance = self.balance.saturating_sub(amount);
+        self.transfers.push((amount, to));
+    }
+
+    #[handler(Command, variant = Close)]
+    async fn close(&mut self, _ctx: &mut Context<Self>) {
+        self.open = false;
+    }
+
+    #[handler(Audit, variant = Reset)]
+    async fn reset(&mut self, _ctx: &mut Context<Self>) {
+        self.balance = 0;
+    }
+}
+
+#[async_trait]
+impl Responder<Balance> for Account {
+    type Response = Option<u64>;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Balance) -> Option<u64> {
+        Some(self.balance).filter(|_| self.open)
+    }
+}
+
+#[async_trait]
+impl Responder<Transfers> for Account {
+    type Response = Vec<(u64, &'static str)>;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Transfers) -> Self::Response {
+        self.transfers.clone()
+    }
+}
+
+#[tokio::test]
+async fn variants_reach_their_methods() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let account = system
+        .add_actor(Account {
+            balance: 0,
+            open: true,
+            transfers: Vec::new(),
+        })
+        .await?;
+
+    account.tell(Command::Deposit { amount: 100 })?;
+    account.tell(Command::Withdraw(30))?;
+    assert_eq!(Some(70), account.ask(Balance).await?);
+
+    account.tell(Command::Transfer {
+        amount: 20,
+        to: "savings",
+    })?;
+    assert_eq!(Some(50), account.ask(Balance).await?);
+    assert_eq!(vec![(20, "savings")], account.ask(Transfers).await?);
+
+    account.tell(Audit::Reset)?;
+    assert_eq!(Some(0), account.ask(Balance).await?);
+
+    account.tell(Command::Close)?;
+    assert_eq!(None, account.ask(Balance).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[derive(Clone, Debug, Dispatch)]
+enum Update<T> {
+    Set(T),
+    Clear,
+}
+
+/// Forwards the values it is given.
+struct Slot<T: Clone + Debug + Send + 'static> {
+    values: UnboundedSender<Option<T>>,
+}
+
+#[actor]
+#[handle(Update<T>, dispatch)]
+impl<T: Clone + Debug + Send + 'static> Slot<T> {
+    #[handler(variant = Set)]
+    async fn set(&mut self, _ctx: &mut Context<Self>, value: T) {
+        self.values.send(Some(value)).unwrap();
+    }
+
+    #[handler(variant = Clear)]
+    async fn clear(&mut self, _ctx: &mut Context<Self>) {
+        self.values.send(None).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn generic_enums_are_dispatched() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut values) = unbounded_channel();
+    let slot = system.add_actor(Slot { values: send }).await?;
+
+    slot.tell(Update::Set("one"))?;
+    slot.tell(Update::Clear)?;
+    assert_eq!(Some(Some("one")), values.recv().await);
+    assert_eq!(Some(None), values.recv().await);
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use ::std::{collections::HashMap, fmt::Debug, hash::Hash};
+use ::tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use ::yaaf::prelude::*;
+
+mod events {
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Update<K, V> {
+        pub key: K,
+        pub value: V,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Replaced<V>(pub V);
+}
+
+#[derive(Clone, Debug)]
+struct Get<K>(K);
+
+/// Caches the latest value for every key, publishing the values it replaces.
+#[derive(Actor)]
+#[handle(events::Update<K, V>)]
+#[publish(events::Replaced<V>)]
+struct Cache<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    entries: HashMap<K, V>,
+}
+
+#[async_trait]
+impl<K, V> Handler<events::Update<K, V>> for Cache<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    async fn handle(&mut self, ctx: &mut Context<Self>, message: events::Update<K, V>) {
+        if let Some(old) = self.entries.insert(message.key, message.value) {
+            ctx.publish(events::Replaced(old)).unwrap();
+        }
+    }
+}
+
+#[async_trait]
+impl<K, V> Responder<Get<K>> for Cache<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Send + Sync + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    type Response = Option<V>;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, message: Get<K>) -> Option<V> {
+        self.entries.get(&message.0).cloned()
+    }
+}
+
+/// Reports every replaced value of one type.
+#[derive(Actor)]
+#[handle(events::Replaced<T>)]
+struct Watcher<T: Clone + Debug + Send + 'static> {
+    replaced: UnboundedSender<T>,
+}
+
+#[async_trait]
+impl<T: Clone + Debug + Send + 'static> Handler<events::Replaced<T>> for Watcher<T> {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: events::Replaced<T>) {
+        self.replaced.send(message.0).unwrap();
+    }
+}
+
+fn update<K, V>(key: K, value: V) -> events::Update<K, V> {
+    events::Update { key, value }
+}
+
+#[tokio::test]
+async fn generic_actors() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let cache = system
+        .add_actor(Cache::<&'static str, u32> {
+            entries: vec![("a", 0)].into_iter().collect(),
+        })
+        .await?;
+    let (send, mut replaced) = unbounded_channel();
+    system.add_actor(Watcher::<u32> { replaced: send }).await?;
+
+    cache.tell(update("a", 1))?;
+    assert_eq!(Some(0), replaced.recv().await);
+    // The first update has been handled, so the published one comes after it.
+    system.publish(update("a", 2_u32))?;
+    assert_eq!(Some(1), replaced.recv().await);
+    assert_eq!(Some(2), cache.ask(Get("a")).await?);
+    assert_eq!(None, cache.ask(Get("b")).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use ::std::collections::HashSet;
+use ::yaaf::{prelude::*, ActorId, ActorOptions};
+
+#[derive(Clone, Debug)]
+struct WhoAmI;
+
+#[derive(Actor)]
+struct Billing;
+
+#[async_trait]
+impl Responder<WhoAmI> for Billing {
+    type Response = (ActorId, Option<String>);
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, _message: WhoAmI) -> Self::Response {
+        (ctx.id(), ctx.name().map(String::from))
+    }
+}
+
+#[tokio::test]
+async fn addresses_compare_by_actor() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let first = system.add_actor(Billing).await?;
+    let second = system.add_actor(Billing).await?;
+    assert_ne!(first.id(), second.id());
+    assert_eq!(first, first.clone());
+    assert_ne!(first, second);
+
+    // Addresses hash by their immutable id, despite the channels inside.
+    #[allow(clippy::mutable_key_type)]
+    let addresses: HashSet<_> = vec![first.clone(), second.clone(), first.clone()]
+        .into_iter()
+        .collect();
+    assert_eq!(2, addresses.len());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn names_are_visible() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let billing = system
+        .add_actor_with(ActorOptions::new(Billing).name("billing"))
+        .await?;
+    assert_eq!(Some("billing"), billing.name());
+    assert_eq!(
+        (billing.id(), Some(String::from("billing"))),
+        billing.ask(WhoAmI).await?
+    );
+
+    let unnamed = system.add_actor(Billing).await?;
+    assert_eq!(None, unnamed.name());
+    assert_eq!((unnamed.id(), None), unnamed.ask(WhoAmI).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn debug_shows_identity() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let billing = system
+        .add_actor_with(ActorOptions::new(Billing).name("billing"))
+        .await?;
+    assert_eq!(
+        format!(
+            "ActorAddress {{ id: {:?}, name: \"billing\", actor: \"identity_test::Billing\" }}",
+            billing.id()
+        ),
+        format!("{:?}", billing)
+    );
+
+    let unnamed = system.add_actor(Billing).await?;
+    assert_eq!(
+        format!(
+            "ActorAddress {{ id: {:?}, actor: \"identity_test::Billing\" }}",
+            unnamed.id()
+        ),
+        format!("{:?}", unnamed)
+    );
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use ::std::sync::Arc;
+use ::tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedSender},
+    oneshot, Notify,
+};
+use ::yaaf::{
+    prelude::*, ActorAddress, ActorMetrics, ActorOptions, DirectRegistered, Lagged,
+    SupervisionStrategy,
+};
+
+#[derive(Clone, Debug)]
+struct Tick(u32);
+
+#[derive(Clone, Debug)]
+struct Hold;
+
+/// Publishes `count` ticks as fast as it can.
+#[derive(Source)]
+#[publish(Tick)]
+struct Clock {
+    count: u32,
+    done: oneshot::Sender<()>,
+}
+
+#[async_trait]
+impl Source for Clock {
+    async fn run(mut self, mut ctx: Context<Self>) {
+        for i in 1..=self.count {
+            ctx.publish(Tick(i)).unwrap();
+        }
+        self.done.send(()).unwrap();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Started,
+    Tick(u32),
+    Lagged(Lagged),
+}
+
+/// Blocks on `Hold` until released, so that it falls behind the clock.
+#[derive(Actor)]
+#[handle(Tick, Hold)]
+struct Counter {
+    events: UnboundedSender<Event>,
+    release: Arc<Notify>,
+}
+
+#[async_trait]
+impl Handler<Tick> for Counter {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Tick) {
+        self.events.send(Event::Tick(message.0)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Hold> for Counter {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Hold) {
+        self.release.notified().await;
+    }
+}
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Tick, Hold)]
+#[lag(policy = "notify")]
+struct Listener {
+    events: UnboundedSender<Event>,
+    release: Arc<Notify>,
+}
+
+#[async_trait]
+impl Actor for Listener {
+    async fn lagged(&mut self, _ctx: &mut Context<Self>, lagged: Lagged) {
+        self.events.send(Event::Lagged(lagged)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Tick> for Listener {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Tick) {
+        self.events.send(Event::Tick(message.0)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Hold> for Listener {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Hold) {
+        self.release.notified().await;
+    }
+}
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Tick, Hold)]
+#[lag(policy = "fail")]
+struct Strict {
+    events: UnboundedSender<Event>,
+    release: Arc<Notify>,
+}
+
+#[async_trait]
+impl Actor for Strict {
+    async fn started(&mut self, _ctx: &mut Context<Self>) {
+        self.events.send(Event::Started).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Tick> for Strict {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Tick) {
+        self.events.send(Event::Tick(message.0)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Hold> for Strict {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Hold) {
+        self.release.notified().await;
+    }
+}
+
+/// Holds the actor while the clock publishes five ticks, then releases it.
+async fn fall_behind<A>(system: &mut System, actor: &ActorAddress<A>, release: &Notify)
+where
+    A: 'static + Handler<Hold> + DirectRegistered<Hold>,
+{
+    actor.tell(Hold).unwrap();
+    // Give the actor a chance to start handling `Hold`.
+    ::tokio::task::yield_now().await;
+
+    let (done, clock_done) = oneshot::channel();
+    system.add_source(Clock { count: 5, done }).await.unwrap();
+    clock_done.await.unwrap();
+
+    release.notify_one();
+}
+
+#[tokio::test]
+async fn lag_is_counted() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::builder().capacity::<Tick>(2).build();
+
+    let (send, mut events) = unbounded_channel();
+    let release = Arc::new(Notify::new());
+    let counter = system
+        .add_actor(Counter {
+            events: send,
+            release: release.clone(),
+        })
+        .await?;
+
+    fall_behind(&mut system, &counter, &release).await;
+
+    assert_eq!(Event::Tick(4), events.recv().await.unwrap());
+    assert_eq!(Event::Tick(5), events.recv().await.unwrap());
+    assert_eq!(
+        ActorMetrics {
+            lag_events: 1,
+            skipped_messages: 3,
+        },
+        counter.metrics()
+    );
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn lag_is_notified() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::builder().capacity::<Tick>(2).build();
+
+    let (send, mut events) = unbounded_channel();
+    let release = Arc::new(Notify::new());
+    let listener = system
+        .add_actor(Listener {
+            events: send,
+            release: release.clone(),
+        })
+        .await?;
+
+    fall_behind(&mut system, &listener, &release).await;
+
+    assert_eq!(
+        Event::Lagged(Lagged {
+            message_type: ::std::any::type_name::<Tick>(),
+            skipped: 3,
+        }),
+        events.recv().await.unwrap()
+    );
+    assert_eq!(Event::Tick(4), events.recv().await.unwrap());
+    assert_eq!(Event::Tick(5), events.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn lag_failure_is_supervised() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::builder().default_capacity(2).build();
+
+    let (send, mut events) = unbounded_channel();
+    let release = Arc::new(Notify::new());
+    let strict = {
+        let release = release.clone();
+        system
+            .add_actor_with(ActorOptions::supervised(
+                move || Strict {
+                    events: send.clone(),
+                    release: release.clone(),
+                },
+                SupervisionStrategy::Restart,
+            ))
+            .await?
+    };
+    assert_eq!(Event::Started, events.recv().await.unwrap());
+
+    fall_behind(&mut system, &strict, &release).await;
+
+    assert_eq!(Event::Started, events.recv().await.unwrap());
+    assert_eq!(Event::Tick(4), events.recv().await.unwrap());
+    assert_eq!(Event::Tick(5), events.recv().await.unwrap());
+    assert_eq!(1, strict.metrics().lag_events);
+
+    system.shutdown().await?;
+    Ok(())
+}
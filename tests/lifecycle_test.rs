@@ -0,0 +1,157 @@
+use ::std::sync::Arc;
+use ::tokio::sync::{
+    mpsc::{channel, Sender},
+    Mutex,
+};
+use ::yaaf::{error::RegistryError, prelude::*, ActorOptions, TerminationReason};
+
+#[derive(Clone, Debug)]
+struct Ping;
+
+#[derive(Clone, Debug)]
+struct Pong;
+
+#[derive(Clone, Debug)]
+struct Started;
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Ping, Pong)]
+#[publish(Started)]
+struct Recorder {
+    events: Arc<Mutex<Vec<&'static str>>>,
+    handled: Sender<()>,
+}
+
+#[async_trait]
+impl Actor for Recorder {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        self.events.lock().await.push("started");
+        ctx.publish(Started).unwrap();
+    }
+
+    async fn stopping(&mut self, _ctx: &mut Context<Self>) {
+        self.events.lock().await.push("stopping");
+    }
+
+    async fn stopped(&mut self) {
+        self.events.lock().await.push("stopped");
+    }
+}
+
+#[async_trait]
+impl Handler<Ping> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Ping) {
+        self.events.lock().await.push("ping");
+        self.handled.send(()).await.unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Pong> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Pong) {
+        self.events.lock().await.push("pong");
+        self.handled.send(()).await.unwrap();
+    }
+}
+
+#[derive(Actor)]
+#[handle(Started)]
+struct Listener {
+    started: Sender<()>,
+}
+
+#[async_trait]
+impl Handler<Started> for Listener {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Started) {
+        self.started.send(()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn lifecycle_hooks() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let (send_started, mut recv_started) = channel(1);
+    system
+        .add_actor(Listener {
+            started: send_started,
+        })
+        .await?;
+
+    let (send_handled, mut recv_handled) = channel(2);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let address = system
+        .add_actor(Recorder {
+            events: events.clone(),
+            handled: send_handled,
+        })
+        .await?;
+
+    address.tell(Ping)?;
+    address.tell(Pong)?;
+
+    recv_started.recv().await.unwrap();
+    recv_handled.recv().await.unwrap();
+    recv_handled.recv().await.unwrap();
+    system.shutdown().await?;
+
+    let events = events.lock().await;
+    assert_eq!(5, events.len());
+    assert_eq!("started", events[0]);
+    assert!(events[1..3].contains(&"ping"));
+    assert!(events[1..3].contains(&"pong"));
+    assert_eq!(["stopping", "stopped"], events[3..]);
+
+    Ok(())
+}
+
+/// Panics while stopping.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Ping)]
+struct Clumsy {
+    events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+#[async_trait]
+impl Actor for Clumsy {
+    async fn stopping(&mut self, _ctx: &mut Context<Self>) {
+        panic!("stopped clumsily");
+    }
+
+    async fn stopped(&mut self) {
+        self.events.lock().await.push("stopped");
+    }
+}
+
+#[async_trait]
+impl Handler<Ping> for Clumsy {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Ping) {}
+}
+
+#[tokio::test]
+async fn panic_while_stopping() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let address = system
+        .add_actor_with(
+            ActorOptions::new(Clumsy {
+                events: events.clone(),
+            })
+            .name("clumsy"),
+        )
+        .await?;
+
+    address.stop()?;
+    assert_eq!(TerminationReason::Panicked, address.closed().await);
+    assert!(matches!(
+        system.lookup::<Clumsy>("clumsy"),
+        Err(RegistryError::NotRegistered { .. })
+    ));
+    assert!(events.lock().await.is_empty());
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use ::std::sync::Arc;
+use ::tokio::sync::{Mutex, Notify};
+use ::yaaf::{
+    error::AddressError, prelude::*, ActorAddress, ActorOptions, MailboxConfig, OverflowPolicy,
+};
+
+#[derive(Clone, Debug)]
+struct Hold;
+
+#[derive(Clone, Debug)]
+struct Value(u32);
+
+#[derive(Clone, Debug)]
+struct GetValues;
+
+/// Blocks on `Hold` until released, so that later messages stay queued.
+#[derive(Actor)]
+#[handle(Hold, Value)]
+struct Recorder {
+    release: Arc<Notify>,
+    values: Vec<u32>,
+}
+
+#[async_trait]
+impl Handler<Hold> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Hold) {
+        self.release.notified().await;
+    }
+}
+
+#[async_trait]
+impl Handler<Value> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Value) {
+        self.values.push(message.0);
+    }
+}
+
+#[async_trait]
+impl Responder<GetValues> for Recorder {
+    type Response = Vec<u32>;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: GetValues) -> Self::Response {
+        self.values.clone()
+    }
+}
+
+#[derive(Actor)]
+#[handle(Value)]
+#[mailbox(capacity = 1, overflow = "fail")]
+struct Small {
+    values: Arc<Mutex<Vec<u32>>>,
+}
+
+#[async_trait]
+impl Handler<Value> for Small {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Value) {
+        self.values.lock().await.push(message.0);
+    }
+}
+
+async fn hold(
+    system: &mut System,
+    overflow: OverflowPolicy,
+) -> Result<(ActorAddress<Recorder>, Arc<Notify>), Box<dyn ::std::error::Error>> {
+    let release = Arc::new(Notify::new());
+    let recorder = system
+        .add_actor_with(
+            ActorOptions::new(Recorder {
+                release: release.clone(),
+                values: Vec::new(),
+            })
+            .mailbox(MailboxConfig::bounded(2, overflow)),
+        )
+        .await?;
+    recorder.tell(Hold)?;
+    // Give the actor a chance to take `Hold` off the queue.
+    ::tokio::task::yield_now().await;
+    Ok((recorder, release))
+}
+
+#[tokio::test]
+async fn fail_rejects_when_full() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (recorder, release) = hold(&mut system, OverflowPolicy::Fail).await?;
+
+    recorder.tell(Value(1))?;
+    recorder.tell(Value(2))?;
+    assert!(matches!(
+        recorder.tell(Value(3)),
+        Err(AddressError::MailboxFull)
+    ));
+
+    release.notify_one();
+    // Let the actor drain its mailbox, so that the ask finds room.
+    ::tokio::task::yield_now().await;
+    assert_eq!(vec![1, 2], recorder.ask(GetValues).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn drop_newest_discards_new_messages() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (recorder, release) = hold(&mut system, OverflowPolicy::DropNewest).await?;
+
+    for i in 1..=4 {
+        recorder.tell(Value(i))?;
+    }
+
+    release.notify_one();
+    // Let the actor drain its mailbox, so that the ask finds room.
+    ::tokio::task::yield_now().await;
+    assert_eq!(vec![1, 2], recorder.ask(GetValues).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn drop_oldest_discards_queued_messages() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (recorder, release) = hold(&mut system, OverflowPolicy::DropOldest).await?;
+
+    for i in 1..=4 {
+        recorder.tell(Value(i))?;
+    }
+
+    release.notify_one();
+    // Let the actor drain its mailbox, so that the ask finds room.
+    ::tokio::task::yield_now().await;
+    assert_eq!(vec![3, 4], recorder.ask(GetValues).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn block_waits_for_room() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (recorder, release) = hold(&mut system, OverflowPolicy::Block).await?;
+
+    recorder.tell(Value(1))?;
+    recorder.tell(Value(2))?;
+    assert!(matches!(
+        recorder.tell(Value(3)),
+        Err(AddressError::MailboxFull)
+    ));
+
+    let waiting = {
+        let recorder = recorder.clone();
+        ::tokio::spawn(async move { recorder.tell_wait(Value(3)).await })
+    };
+    release.notify_one();
+    waiting.await??;
+
+    assert_eq!(vec![1, 2, 3], recorder.ask(GetValues).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn derive_configures_mailbox() -> Result<(), Box<dyn ::std::error::Error>> {
+    assert_eq!(
+        MailboxConfig::bounded(1, OverflowPolicy::Fail),
+        <Small as ::yaaf::ActorMeta>::mailbox_config()
+    );
+    assert_eq!(
+        MailboxConfig::unbounded(),
+        <Recorder as ::yaaf::ActorMeta>::mailbox_config()
+    );
+
+    let mut system = System::new();
+    let values = Arc::new(Mutex::new(Vec::new()));
+    let small = system
+        .add_actor(Small {
+            values: values.clone(),
+        })
+        .await?;
+    small.tell(Value(1))?;
+
+    system.shutdown().await?;
+    Ok(())
+}
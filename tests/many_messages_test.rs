@@ -0,0 +1,73 @@
+use ::tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use ::yaaf::prelude::*;
+
+macro_rules! messages {
+    ($( $message:ident = $value:expr ),*) => {
+        $(
+            #[derive(Clone, Debug)]
+            struct $message;
+
+            #[async_trait]
+            impl Handler<$message> for Collector {
+                async fn handle(&mut self, _ctx: &mut Context<Self>, _message: $message) {
+                    self.received.send($value).unwrap();
+                }
+            }
+        )*
+    };
+}
+
+messages!(
+    M1 = 1,
+    M2 = 2,
+    M3 = 3,
+    M4 = 4,
+    M5 = 5,
+    M6 = 6,
+    M7 = 7,
+    M8 = 8,
+    M9 = 9,
+    M10 = 10,
+    M11 = 11,
+    M12 = 12
+);
+
+/// Handles more message types than fit in a tuple list.
+#[derive(Actor)]
+#[handle(M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11)]
+#[handle(M12, direct_only)]
+struct Collector {
+    received: UnboundedSender<u32>,
+}
+
+/// Publishes more message types than fit in a tuple list.
+#[derive(Source)]
+#[publish(M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11)]
+struct Emitter;
+
+#[async_trait]
+impl Source for Emitter {
+    async fn run(mut self, mut ctx: Context<Self>) {
+        ctx.publish(M1).unwrap();
+        ctx.publish(M11).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn more_than_ten_messages() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut received) = unbounded_channel();
+    let collector = system.add_actor(Collector { received: send }).await?;
+
+    system.publish(M10)?;
+    assert_eq!(Some(10), received.recv().await);
+    collector.tell(M12)?;
+    assert_eq!(Some(12), received.recv().await);
+
+    system.add_source(Emitter).await?;
+    assert_eq!(Some(1), received.recv().await);
+    assert_eq!(Some(11), received.recv().await);
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use ::tokio::sync::oneshot::{channel, Sender};
+use ::yaaf::prelude::*;
+
+#[derive(Clone, Debug)]
+struct MessageA(u32);
+
+#[derive(Clone, Debug)]
+struct MessageB(u32);
+
+#[derive(Clone, Debug)]
+struct Finish;
+
+#[derive(Actor)]
+#[handle(MessageA, MessageB, Finish)]
+struct Recorder {
+    received: Vec<u32>,
+    done: Option<Sender<Vec<u32>>>,
+}
+
+#[async_trait]
+impl Handler<MessageA> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: MessageA) {
+        self.received.push(message.0);
+    }
+}
+
+#[async_trait]
+impl Handler<MessageB> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: MessageB) {
+        self.received.push(message.0);
+    }
+}
+
+#[async_trait]
+impl Handler<Finish> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Finish) {
+        if let Some(done) = self.done.take() {
+            done.send(self.received.clone()).unwrap();
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tells_preserve_order_across_types() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let (send, recv) = channel();
+    let address = system
+        .add_actor(Recorder {
+            received: Vec::new(),
+            done: Some(send),
+        })
+        .await?;
+
+    for i in 0..100 {
+        if i % 3 == 0 {
+            address.tell(MessageA(i))?;
+        } else {
+            address.tell(MessageB(i))?;
+        }
+    }
+    address.tell(Finish)?;
+
+    let received = recv.await?;
+    system.shutdown().await?;
+
+    assert_eq!((0..100).collect::<Vec<_>>(), received);
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use ::tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+    time::Instant,
+};
+use ::yaaf::{prelude::*, Headers, Metadata, Origin};
+
+#[derive(Clone, Debug)]
+struct Request;
+
+#[derive(Clone, Debug)]
+struct Event;
+
+#[derive(Clone, Debug)]
+struct Inspect;
+
+fn headers(pairs: &[(&str, &str)]) -> Headers {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Reports the metadata of each request, and publishes an event in
+/// response.
+#[derive(Actor)]
+#[handle(Request)]
+#[publish(Event)]
+struct Relay {
+    requests: UnboundedSender<Metadata>,
+}
+
+#[async_trait]
+impl Handler<Request> for Relay {
+    async fn handle(&mut self, ctx: &mut Context<Self>, _message: Request) {
+        self.requests.send(ctx.envelope().unwrap().clone()).unwrap();
+        ctx.publish_with(Event, headers(&[("hop", "relay")]))
+            .unwrap();
+    }
+}
+
+/// Reports the metadata of each event, and of each request it is asked.
+#[derive(Actor)]
+#[handle(Event)]
+struct Recorder {
+    events: UnboundedSender<Metadata>,
+}
+
+#[async_trait]
+impl Handler<Event> for Recorder {
+    async fn handle(&mut self, ctx: &mut Context<Self>, _message: Event) {
+        self.events.send(ctx.envelope().unwrap().clone()).unwrap();
+    }
+}
+
+#[async_trait]
+impl Responder<Inspect> for Recorder {
+    type Response = Option<Metadata>;
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, _message: Inspect) -> Self::Response {
+        ctx.envelope().cloned()
+    }
+}
+
+#[tokio::test]
+async fn direct_message_metadata() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut requests) = unbounded_channel();
+    let relay = system.add_actor(Relay { requests: send }).await?;
+    let (send, _events) = unbounded_channel();
+    system.add_actor(Recorder { events: send }).await?;
+
+    let before = Instant::now();
+    relay.tell_with(Request, headers(&[("tenant", "acme")]))?;
+    relay.tell(Request)?;
+
+    let first = requests.recv().await.unwrap();
+    assert_eq!(None, first.sender);
+    assert_eq!(Origin::Direct, first.origin);
+    assert_eq!(headers(&[("tenant", "acme")]), first.headers);
+    assert!(first.enqueued_at >= before && first.enqueued_at <= Instant::now());
+
+    // Each message sent from outside starts its own chain.
+    let second = requests.recv().await.unwrap();
+    assert!(second.headers.is_empty());
+    assert_ne!(first.correlation_id, second.correlation_id);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn publish_in_response_propagates() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut requests) = unbounded_channel();
+    let relay = system.add_actor(Relay { requests: send }).await?;
+    let (send, mut events) = unbounded_channel();
+    system.add_actor(Recorder { events: send }).await?;
+
+    relay.tell_with(Request, headers(&[("tenant", "acme")]))?;
+    let request = requests.recv().await.unwrap();
+    let event = events.recv().await.unwrap();
+
+    assert_eq!(Some(relay.id()), event.sender);
+    assert_eq!(Origin::Broadcast, event.origin);
+    assert_eq!(request.correlation_id, event.correlation_id);
+    assert_eq!(
+        headers(&[("hop", "relay"), ("tenant", "acme")]),
+        event.headers
+    );
+    assert!(event.enqueued_at >= request.enqueued_at);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn published_from_outside() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut events) = unbounded_channel();
+    let recorder = system.add_actor(Recorder { events: send }).await?;
+
+    system.publish(Event)?;
+    let event = events.recv().await.unwrap();
+    assert_eq!(None, event.sender);
+    assert_eq!(Origin::Broadcast, event.origin);
+
+    let asked = recorder.ask(Inspect).await?.unwrap();
+    assert_eq!(Origin::Direct, asked.origin);
+    assert_ne!(event.correlation_id, asked.correlation_id);
+
+    system.shutdown().await?;
+    Ok(())
+}
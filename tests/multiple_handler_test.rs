@@ -75,8 +75,8 @@ async fn multiple_handler() -> Result<(), Box<dyn ::std::error::Error>> {
     recv.recv().await.unwrap();
     system.shutdown().await?;
 
-    assert_eq!(true, *got_a.lock().await);
-    assert_eq!(true, *got_b.lock().await);
+    assert!(*got_a.lock().await);
+    assert!(*got_b.lock().await);
 
     Ok(())
 }
@@ -0,0 +1,164 @@
+use ::yaaf::{
+    error::{RegistryError, SystemError},
+    prelude::*,
+    ActorAddress, ActorOptions, TerminationReason,
+};
+
+#[derive(Clone, Debug)]
+struct Charge(u32);
+
+#[derive(Clone, Debug)]
+struct Total;
+
+#[derive(Clone, Debug)]
+struct Checkout(u32);
+
+#[derive(Actor)]
+#[handle(Charge)]
+struct Billing {
+    total: u32,
+}
+
+#[async_trait]
+impl Handler<Charge> for Billing {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Charge) {
+        self.total += message.0;
+    }
+}
+
+#[async_trait]
+impl Responder<Total> for Billing {
+    type Response = u32;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Total) -> Self::Response {
+        self.total
+    }
+}
+
+/// Finds billing by name rather than holding its address.
+#[derive(Actor)]
+struct Shop;
+
+#[async_trait]
+impl Responder<Checkout> for Shop {
+    type Response = Result<(), RegistryError>;
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, message: Checkout) -> Self::Response {
+        let billing = ctx.system().lookup::<Billing>("billing")?;
+        billing.tell(Charge(message.0)).unwrap();
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn lookup_from_system() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let billing = system
+        .add_actor_with(ActorOptions::new(Billing { total: 0 }).name("billing"))
+        .await?;
+
+    let found = system.lookup::<Billing>("billing")?;
+    assert_eq!(billing, found);
+    found.tell(Charge(3))?;
+    assert_eq!(3, billing.ask(Total).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn lookup_from_handler() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let shop = system.add_actor(Shop).await?;
+
+    assert!(matches!(
+        shop.ask(Checkout(5)).await?,
+        Err(RegistryError::NotRegistered { .. })
+    ));
+
+    let billing = system
+        .add_actor_with(ActorOptions::new(Billing { total: 0 }).name("billing"))
+        .await?;
+    shop.ask(Checkout(5)).await??;
+    assert_eq!(5, billing.ask(Total).await?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn lookup_checks_type() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    system
+        .add_actor_with(ActorOptions::new(Shop).name("shop"))
+        .await?;
+
+    assert!(matches!(
+        system.lookup::<Billing>("shop"),
+        Err(RegistryError::WrongType { .. })
+    ));
+    assert!(system.lookup::<Shop>("shop").is_ok());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn names_are_unique() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let billing = system
+        .add_actor_with(ActorOptions::new(Billing { total: 0 }).name("billing"))
+        .await?;
+
+    assert!(matches!(
+        system
+            .add_actor_with(ActorOptions::new(Billing { total: 0 }).name("billing"))
+            .await,
+        Err(SystemError::RegisterFailure {
+            source: RegistryError::NameTaken { .. }
+        })
+    ));
+
+    let other = system.add_actor(Billing { total: 0 }).await?;
+    assert!(matches!(
+        system.register("billing", &other),
+        Err(RegistryError::NameTaken { .. })
+    ));
+    system.register("billing", &billing)?;
+    system.register("backup", &other)?;
+    assert_eq!(other, system.lookup::<Billing>("backup")?);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn stopped_actors_are_removed() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let billing: ActorAddress<Billing> = system
+        .add_actor_with(ActorOptions::new(Billing { total: 0 }).name("billing"))
+        .await?;
+    system.register("invoices", &billing)?;
+
+    billing.stop()?;
+    assert_eq!(TerminationReason::Stopped, billing.closed().await);
+
+    for name in ["billing", "invoices"] {
+        assert!(matches!(
+            system.lookup::<Billing>(name),
+            Err(RegistryError::NotRegistered { .. })
+        ));
+    }
+    assert!(matches!(
+        system.register("billing", &billing),
+        Err(RegistryError::ActorStopped)
+    ));
+
+    let replacement = system
+        .add_actor_with(ActorOptions::new(Billing { total: 0 }).name("billing"))
+        .await?;
+    assert_eq!(replacement, system.lookup::<Billing>("billing")?);
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use ::tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use ::yaaf::{error::SystemError, prelude::*};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Command(u32);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Event(u32);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Note(u32);
+
+#[derive(Debug, PartialEq)]
+enum Received {
+    Command(u32),
+    Event(u32),
+    Note(u32),
+}
+
+/// Takes commands only when told, events only when published, and notes
+/// either way.
+#[derive(Actor)]
+#[handle(Command, direct_only)]
+#[subscribe(Event)]
+#[handle(Note)]
+struct Worker {
+    received: UnboundedSender<Received>,
+}
+
+#[async_trait]
+impl Handler<Command> for Worker {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Command) {
+        self.received.send(Received::Command(message.0)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Event> for Worker {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Event) {
+        self.received.send(Received::Event(message.0)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Note> for Worker {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Note) {
+        self.received.send(Received::Note(message.0)).unwrap();
+    }
+}
+
+/// Takes events published to one topic, and notes only when told.
+#[derive(Actor)]
+#[subscribe(Event, topic = "audit")]
+#[handle(Note, direct_only)]
+struct Auditor {
+    received: UnboundedSender<Received>,
+}
+
+#[async_trait]
+impl Handler<Event> for Auditor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Event) {
+        self.received.send(Received::Event(message.0)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Note> for Auditor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Note) {
+        self.received.send(Received::Note(message.0)).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn inputs_are_selected() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut received) = unbounded_channel();
+    let worker = system.add_actor(Worker { received: send }).await?;
+
+    // Nothing subscribes to commands.
+    assert!(matches!(
+        system.publish(Command(1)),
+        Err(SystemError::PublishFailure { .. })
+    ));
+    worker.tell(Command(2))?;
+    assert_eq!(Received::Command(2), received.recv().await.unwrap());
+
+    system.publish(Event(3))?;
+    assert_eq!(Received::Event(3), received.recv().await.unwrap());
+
+    worker.tell(Note(4))?;
+    assert_eq!(Received::Note(4), received.recv().await.unwrap());
+    system.publish(Note(5))?;
+    assert_eq!(Received::Note(5), received.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn subscribe_takes_topics() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut received) = unbounded_channel();
+    let auditor = system.add_actor(Auditor { received: send }).await?;
+
+    assert!(matches!(
+        system.publish(Event(1)),
+        Err(SystemError::PublishFailure { .. })
+    ));
+    system.publish_to("audit", Event(2))?;
+    assert_eq!(Received::Event(2), received.recv().await.unwrap());
+
+    assert!(matches!(
+        system.publish(Note(3)),
+        Err(SystemError::PublishFailure { .. })
+    ));
+    auditor.tell(Note(4))?;
+    assert_eq!(Received::Note(4), received.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use ::std::{sync::Arc, time::Duration};
+use ::tokio::{
+    select,
+    sync::{oneshot, Mutex},
+    time::sleep,
+};
+use ::yaaf::{error::SystemError, prelude::*, ShutdownMode, TerminationReason};
+
+#[derive(Clone, Debug)]
+struct Tick;
+
+/// Publishes ticks until the system shuts down.
+#[derive(Source)]
+#[publish(Tick)]
+struct Clock {
+    events: Arc<Mutex<Vec<&'static str>>>,
+    started: Option<oneshot::Sender<()>>,
+}
+
+#[async_trait]
+impl Source for Clock {
+    async fn run(mut self, mut ctx: Context<Self>) {
+        if let Some(started) = self.started.take() {
+            started.send(()).unwrap();
+        }
+        let shutdown = ctx.shutdown_requested();
+        ::tokio::pin!(shutdown);
+        loop {
+            select! {
+                () = &mut shutdown => break,
+                () = sleep(Duration::from_millis(1)) => ctx.publish(Tick).unwrap(),
+            }
+        }
+        // Sources may still publish while they wind down.
+        ctx.publish(Tick).unwrap();
+        self.events.lock().await.push("clock stopped");
+    }
+}
+
+/// Publishes one tick, then sleeps without heeding a shutdown.
+#[derive(Source)]
+#[publish(Tick)]
+struct Sleeper;
+
+#[async_trait]
+impl Source for Sleeper {
+    async fn run(mut self, mut ctx: Context<Self>) {
+        ctx.publish(Tick).unwrap();
+        sleep(Duration::from_secs(3600)).await;
+    }
+}
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Tick)]
+struct Named {
+    name: &'static str,
+    events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+#[async_trait]
+impl Actor for Named {
+    async fn stopped(&mut self) {
+        self.events.lock().await.push(self.name);
+    }
+}
+
+#[async_trait]
+impl Handler<Tick> for Named {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Tick) {}
+}
+
+fn named(name: &'static str, events: &Arc<Mutex<Vec<&'static str>>>) -> Named {
+    Named {
+        name,
+        events: events.clone(),
+    }
+}
+
+#[tokio::test]
+async fn sources_stop_before_actors() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    system.add_actor(named("actor", &events)).await?;
+    let (started, clock_started) = oneshot::channel();
+    system
+        .add_source(Clock {
+            events: events.clone(),
+            started: Some(started),
+        })
+        .await?;
+    clock_started.await?;
+
+    system.shutdown_with(ShutdownMode::Graceful).await?;
+
+    assert_eq!(vec!["clock stopped", "actor"], *events.lock().await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn actors_stop_in_reverse_order() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    system.add_actor(named("a", &events)).await?;
+    system.add_actor(named("b", &events)).await?;
+    system.add_actor(named("c", &events)).await?;
+
+    system.shutdown().await?;
+
+    assert_eq!(vec!["c", "b", "a"], *events.lock().await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn dependents_stop_first() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let a = system.add_actor(named("a", &events)).await?;
+    system.add_actor(named("b", &events)).await?;
+    let c = system.add_actor(named("c", &events)).await?;
+    system.add_dependency(a.id(), c.id())?;
+
+    assert!(matches!(
+        system.add_dependency(c.id(), a.id()),
+        Err(SystemError::DependencyCycle { .. })
+    ));
+
+    system.shutdown().await?;
+
+    assert_eq!(vec!["b", "a", "c"], *events.lock().await);
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_source_aborts_actors() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let actor = system.add_actor(named("actor", &events)).await?;
+    system.add_source(Sleeper).await?;
+
+    let report = system
+        .shutdown_with(ShutdownMode::GracefulWithTimeout(Duration::from_millis(50)))
+        .await?;
+
+    assert!(report.aborted);
+    assert_eq!(TerminationReason::Aborted, actor.closed().await);
+    Ok(())
+}
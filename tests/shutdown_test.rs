@@ -0,0 +1,197 @@
+use ::std::{sync::Arc, time::Duration};
+use ::tokio::{
+    join,
+    sync::{Mutex, Notify},
+    task::yield_now,
+};
+use ::yaaf::{error::ContextError, prelude::*, ActorAddress, ShutdownMode};
+
+#[derive(Clone, Debug)]
+struct Hold;
+
+#[derive(Clone, Debug)]
+struct Value(u32);
+
+#[derive(Clone, Debug)]
+struct Farewell;
+
+#[derive(Default)]
+struct Record {
+    values: Vec<u32>,
+    publish_rejected: bool,
+    stopped: bool,
+}
+
+/// Blocks on `Hold` until released, so that later messages stay queued.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Hold, Value)]
+#[publish(Farewell)]
+struct Recorder {
+    record: Arc<Mutex<Record>>,
+    release: Arc<Notify>,
+}
+
+#[async_trait]
+impl Actor for Recorder {
+    async fn stopping(&mut self, ctx: &mut Context<Self>) {
+        let rejected = matches!(ctx.publish(Farewell), Err(ContextError::ShuttingDown));
+        self.record.lock().await.publish_rejected = rejected;
+    }
+
+    async fn stopped(&mut self) {
+        self.record.lock().await.stopped = true;
+    }
+}
+
+#[async_trait]
+impl Handler<Hold> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Hold) {
+        self.release.notified().await;
+    }
+}
+
+#[async_trait]
+impl Handler<Value> for Recorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Value) {
+        self.record.lock().await.values.push(message.0);
+    }
+}
+
+async fn held(
+    system: &mut System,
+) -> Result<(ActorAddress<Recorder>, Arc<Mutex<Record>>, Arc<Notify>), Box<dyn ::std::error::Error>>
+{
+    let record = Arc::new(Mutex::new(Record::default()));
+    let release = Arc::new(Notify::new());
+    let recorder = system
+        .add_actor(Recorder {
+            record: record.clone(),
+            release: release.clone(),
+        })
+        .await?;
+
+    recorder.tell(Hold)?;
+    // Give the actor a chance to start handling `Hold`.
+    yield_now().await;
+    for i in 1..=3 {
+        recorder.tell(Value(i))?;
+    }
+    Ok((recorder, record, release))
+}
+
+#[tokio::test]
+async fn graceful_shutdown_drains_mailbox() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (_recorder, record, release) = held(&mut system).await?;
+
+    let (report, ()) = join!(system.shutdown_with(ShutdownMode::Graceful), async {
+        yield_now().await;
+        release.notify_one();
+    });
+    let report = report?;
+
+    assert!(report.dropped.is_empty());
+    assert!(!report.aborted);
+    let record = record.lock().await;
+    assert_eq!(vec![1, 2, 3], record.values);
+    assert!(record.publish_rejected);
+    assert!(record.stopped);
+    Ok(())
+}
+
+#[tokio::test]
+async fn immediate_shutdown_reports_dropped_messages() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (recorder, record, release) = held(&mut system).await?;
+
+    let (report, ()) = join!(system.shutdown_with(ShutdownMode::Immediate), async {
+        yield_now().await;
+        release.notify_one();
+    });
+    let report = report?;
+
+    let handled = record.lock().await.values.len();
+    let dropped = report.dropped.get(&recorder.id()).copied().unwrap_or(0);
+    assert!(dropped > 0);
+    assert_eq!(3, handled + dropped);
+    assert_eq!(dropped, report.total_dropped());
+    assert!(!report.aborted);
+    Ok(())
+}
+
+fn is_even(value: &Value) -> bool {
+    value.0.is_multiple_of(2)
+}
+
+/// Like [`Recorder`], but only receives even published values.
+#[derive(Actor)]
+#[handle(Hold)]
+#[handle(Value, filter = "is_even")]
+struct EvenRecorder {
+    record: Arc<Mutex<Record>>,
+    release: Arc<Notify>,
+}
+
+#[async_trait]
+impl Handler<Hold> for EvenRecorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Hold) {
+        self.release.notified().await;
+    }
+}
+
+#[async_trait]
+impl Handler<Value> for EvenRecorder {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Value) {
+        self.record.lock().await.values.push(message.0);
+    }
+}
+
+#[tokio::test]
+async fn filtered_messages_are_not_dropped() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let record = Arc::new(Mutex::new(Record::default()));
+    let release = Arc::new(Notify::new());
+    let recorder = system
+        .add_actor(EvenRecorder {
+            record: record.clone(),
+            release: release.clone(),
+        })
+        .await?;
+
+    recorder.tell(Hold)?;
+    yield_now().await;
+    for i in 1..=6 {
+        system.publish(Value(i))?;
+    }
+
+    let (report, ()) = join!(system.shutdown_with(ShutdownMode::Immediate), async {
+        yield_now().await;
+        release.notify_one();
+    });
+    let report = report?;
+
+    // Only the three even values count, whether handled or dropped.
+    let handled = record.lock().await.values.len();
+    let dropped = report.dropped.get(&recorder.id()).copied().unwrap_or(0);
+    assert!(dropped > 0);
+    assert_eq!(3, handled + dropped);
+    Ok(())
+}
+
+#[tokio::test]
+async fn graceful_shutdown_aborts_after_timeout() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (recorder, record, _release) = held(&mut system).await?;
+
+    let report = system
+        .shutdown_with(ShutdownMode::GracefulWithTimeout(Duration::from_millis(50)))
+        .await?;
+
+    assert!(report.aborted);
+    assert_eq!(Some(&3), report.dropped.get(&recorder.id()));
+    let record = record.lock().await;
+    assert!(record.values.is_empty());
+    assert!(!record.stopped);
+    Ok(())
+}
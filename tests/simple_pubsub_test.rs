@@ -67,6 +67,6 @@ async fn simple_pubsub() -> Result<(), Box<dyn ::std::error::Error>> {
     recv.await.unwrap();
     system.shutdown().await?;
 
-    assert_eq!(true, *visited.lock().await);
+    assert!(*visited.lock().await);
     Ok(())
 }
@@ -46,7 +46,7 @@ async fn simple_tell() -> Result<(), Box<dyn ::std::error::Error>> {
     recv.recv().await.unwrap();
     system.shutdown().await?;
 
-    assert_eq!(true, *visited.lock().await);
+    assert!(*visited.lock().await);
 
     Ok(())
 }
@@ -0,0 +1,100 @@
+use ::std::sync::Arc;
+use ::tokio::sync::{oneshot, Mutex};
+use ::yaaf::{error::AddressError, prelude::*};
+
+#[derive(Clone, Debug)]
+struct Work;
+
+#[derive(Clone, Debug)]
+struct Quit;
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Work, Quit)]
+struct Worker {
+    events: Arc<Mutex<Vec<&'static str>>>,
+    stopped: Option<oneshot::Sender<()>>,
+}
+
+#[async_trait]
+impl Actor for Worker {
+    async fn stopping(&mut self, _ctx: &mut Context<Self>) {
+        self.events.lock().await.push("stopping");
+    }
+
+    async fn stopped(&mut self) {
+        self.events.lock().await.push("stopped");
+        if let Some(stopped) = self.stopped.take() {
+            stopped.send(()).unwrap();
+        }
+    }
+}
+
+#[async_trait]
+impl Handler<Work> for Worker {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Work) {
+        self.events.lock().await.push("worked");
+    }
+}
+
+#[async_trait]
+impl Handler<Quit> for Worker {
+    async fn handle(&mut self, ctx: &mut Context<Self>, _message: Quit) {
+        ctx.stop();
+    }
+}
+
+fn worker(events: &Arc<Mutex<Vec<&'static str>>>) -> (Worker, oneshot::Receiver<()>) {
+    let (stopped, recv_stopped) = oneshot::channel();
+    let worker = Worker {
+        events: events.clone(),
+        stopped: Some(stopped),
+    };
+    (worker, recv_stopped)
+}
+
+#[tokio::test]
+async fn stop_through_address() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let (stopping, stopped) = worker(&events);
+    let stopping = system.add_actor(stopping).await?;
+    let (running, _) = worker(&events);
+    let running = system.add_actor(running).await?;
+
+    stopping.tell(Work)?;
+    stopping.stop()?;
+    assert!(matches!(
+        stopping.tell(Work),
+        Err(AddressError::ActorStopped)
+    ));
+    assert!(matches!(stopping.stop(), Err(AddressError::ActorStopped)));
+    stopped.await?;
+
+    assert_eq!(vec!["worked", "stopping", "stopped"], *events.lock().await);
+
+    // Other actors are unaffected.
+    running.tell(Work)?;
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn stop_through_context() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let (worker, stopped) = worker(&events);
+    let worker = system.add_actor(worker).await?;
+
+    worker.tell(Quit)?;
+    stopped.await?;
+    assert!(matches!(worker.tell(Work), Err(AddressError::ActorStopped)));
+
+    assert_eq!(vec!["stopping", "stopped"], *events.lock().await);
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use ::futures::{channel::mpsc as stream_channel, stream};
+use ::std::time::Duration;
+use ::tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+    task::yield_now,
+    time::timeout,
+};
+use ::yaaf::{prelude::*, StreamFinished, StreamId};
+
+#[derive(Clone, Debug)]
+struct Line(&'static str);
+
+#[derive(Clone, Debug)]
+struct ReadLines(Vec<&'static str>);
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Line(&'static str),
+    Finished(StreamId),
+}
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Line)]
+struct Reader {
+    events: UnboundedSender<Event>,
+    lines: Option<stream_channel::UnboundedReceiver<Line>>,
+}
+
+#[async_trait]
+impl Actor for Reader {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        if let Some(lines) = self.lines.take() {
+            ctx.add_stream(lines);
+        }
+    }
+
+    async fn stream_finished(&mut self, _ctx: &mut Context<Self>, finished: StreamFinished) {
+        self.events.send(Event::Finished(finished.stream)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Line> for Reader {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Line) {
+        self.events.send(Event::Line(message.0)).unwrap();
+    }
+}
+
+#[async_trait]
+impl Responder<ReadLines> for Reader {
+    type Response = StreamId;
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, message: ReadLines) -> Self::Response {
+        ctx.add_stream(stream::iter(message.0.into_iter().map(Line)))
+            .id()
+    }
+}
+
+#[tokio::test]
+async fn stream_feeds_handler() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut events) = unbounded_channel();
+    let reader = system
+        .add_actor(Reader {
+            events: send,
+            lines: None,
+        })
+        .await?;
+
+    let id = reader.ask(ReadLines(vec!["one", "two"])).await?;
+    assert_eq!(Event::Line("one"), events.recv().await.unwrap());
+    assert_eq!(Event::Line("two"), events.recv().await.unwrap());
+    assert_eq!(Event::Finished(id), events.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn stream_cancelled_on_stop() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut events) = unbounded_channel();
+    let (lines, recv_lines) = stream_channel::unbounded();
+    let reader = system
+        .add_actor(Reader {
+            events: send,
+            lines: Some(recv_lines),
+        })
+        .await?;
+
+    lines.unbounded_send(Line("one"))?;
+    assert_eq!(Event::Line("one"), events.recv().await.unwrap());
+
+    reader.stop()?;
+    reader.closed().await;
+    // The stream is dropped once its task is cancelled.
+    timeout(Duration::from_secs(1), async {
+        while !lines.is_closed() {
+            yield_now().await;
+        }
+    })
+    .await?;
+    assert!(events.recv().await.is_none());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn stream_source_publishes() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut events) = unbounded_channel();
+    system
+        .add_actor(Reader {
+            events: send,
+            lines: None,
+        })
+        .await?;
+
+    system
+        .add_stream_source(stream::iter(vec![Line("one"), Line("two")]))
+        .await?;
+    assert_eq!(Event::Line("one"), events.recv().await.unwrap());
+    assert_eq!(Event::Line("two"), events.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
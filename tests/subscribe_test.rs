@@ -0,0 +1,75 @@
+use ::futures::{pin_mut, StreamExt};
+use ::yaaf::{error::SystemError, prelude::*, Lagged, ShutdownMode};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Question(u32);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Answer(u32);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Tick(u32);
+
+/// Publishes an answer to every question.
+#[derive(Actor)]
+#[handle(Question)]
+#[publish(Answer)]
+struct Oracle;
+
+#[async_trait]
+impl Handler<Question> for Oracle {
+    async fn handle(&mut self, ctx: &mut Context<Self>, message: Question) {
+        ctx.publish(Answer(message.0 * 2)).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn observe_from_outside() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    system.add_actor(Oracle).await?;
+
+    let answers = system.subscribe::<Answer>()?;
+    pin_mut!(answers);
+    system.publish(Question(21))?;
+    assert_eq!(Some(Ok(Answer(42))), answers.next().await);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn lag_is_an_error_item() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::builder().capacity::<Tick>(2).build();
+
+    let ticks = system.subscribe::<Tick>()?;
+    pin_mut!(ticks);
+    for i in 1..=5 {
+        system.publish(Tick(i))?;
+    }
+    assert_eq!(
+        Some(Err(Lagged {
+            message_type: ::std::any::type_name::<Tick>(),
+            skipped: 3,
+        })),
+        ticks.next().await
+    );
+    assert_eq!(Some(Ok(Tick(4))), ticks.next().await);
+    assert_eq!(Some(Ok(Tick(5))), ticks.next().await);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn publish_after_shutdown() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let _ticks = system.subscribe::<Tick>()?;
+
+    system.publish(Tick(1))?;
+    system.shutdown_with(ShutdownMode::Graceful).await?;
+    assert!(matches!(
+        system.publish(Tick(2)),
+        Err(SystemError::ShuttingDown)
+    ));
+    Ok(())
+}
@@ -0,0 +1,318 @@
+use ::std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use ::tokio::time::{sleep, Instant};
+use ::yaaf::{
+    error::AddressError, prelude::*, ActorOptions, MailboxConfig, OverflowPolicy,
+    SupervisionStrategy, TerminationReason, MAX_FAILED_STARTS,
+};
+
+#[derive(Clone, Debug)]
+struct Increment;
+
+#[derive(Clone, Debug)]
+struct Crash;
+
+#[derive(Clone, Debug)]
+struct Count;
+
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Increment, Crash)]
+struct Fragile {
+    count: u32,
+    starts: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Actor for Fragile {
+    async fn started(&mut self, _ctx: &mut Context<Self>) {
+        self.starts.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Handler<Increment> for Fragile {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Increment) {
+        self.count += 1;
+    }
+}
+
+#[async_trait]
+impl Handler<Crash> for Fragile {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Crash) {
+        panic!("crashed on purpose");
+    }
+}
+
+#[async_trait]
+impl Responder<Count> for Fragile {
+    type Response = u32;
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Count) -> u32 {
+        self.count
+    }
+}
+
+fn factory(starts: Arc<AtomicUsize>) -> impl FnMut() -> Fragile + Send + 'static {
+    move || Fragile {
+        count: 0,
+        starts: starts.clone(),
+    }
+}
+
+#[tokio::test]
+async fn restart_keeps_address() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let address = system
+        .add_actor_with(ActorOptions::supervised(
+            factory(starts.clone()),
+            SupervisionStrategy::Restart,
+        ))
+        .await?;
+
+    address.tell(Increment)?;
+    address.tell(Increment)?;
+    assert_eq!(2, address.ask(Count).await?);
+
+    address.tell(Crash)?;
+    address.tell(Increment)?;
+    assert_eq!(1, address.ask(Count).await?);
+    assert_eq!(2, starts.load(Ordering::SeqCst));
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn supervised_actor_can_be_named_with_bounded_mailbox(
+) -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let address = system
+        .add_actor_with(
+            ActorOptions::supervised(factory(starts.clone()), SupervisionStrategy::Restart)
+                .name("fragile")
+                .mailbox(MailboxConfig::bounded(1, OverflowPolicy::Block)),
+        )
+        .await?;
+
+    address.tell(Crash)?;
+    assert!(matches!(
+        address.tell(Increment),
+        Err(AddressError::MailboxFull)
+    ));
+    assert_eq!(0, address.ask(Count).await?);
+    assert_eq!(2, starts.load(Ordering::SeqCst));
+
+    // The replacement keeps both the name and the mailbox.
+    assert_eq!(address, system.lookup::<Fragile>("fragile")?);
+    address.tell(Increment)?;
+    assert!(matches!(
+        address.tell(Increment),
+        Err(AddressError::MailboxFull)
+    ));
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+/// Checks that about `delay` has passed since `since`, allowing for the
+/// timer's resolution.
+fn assert_waited(delay: Duration, since: Instant) {
+    let waited = since.elapsed();
+    assert!(
+        waited >= delay && waited < delay + Duration::from_millis(50),
+        "waited {:?} rather than {:?}",
+        waited,
+        delay
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn restart_with_backoff() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let address = system
+        .add_actor_with(ActorOptions::supervised(
+            factory(starts.clone()),
+            SupervisionStrategy::RestartWithBackoff {
+                min: Duration::from_millis(100),
+                max: Duration::from_millis(400),
+            },
+        ))
+        .await?;
+
+    // The delay starts at `min` and doubles with each consecutive panic, up
+    // to `max`.
+    for expected in [100, 200, 400, 400] {
+        let crashed = Instant::now();
+        address.tell(Crash)?;
+        assert_eq!(0, address.ask(Count).await?);
+        assert_waited(Duration::from_millis(expected), crashed);
+    }
+    assert_eq!(5, starts.load(Ordering::SeqCst));
+
+    // Running for longer than `max` resets the delay.
+    sleep(Duration::from_millis(500)).await;
+    let crashed = Instant::now();
+    address.tell(Crash)?;
+    assert_eq!(0, address.ask(Count).await?);
+    assert_waited(Duration::from_millis(100), crashed);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn shutdown_interrupts_backoff() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let address = system
+        .add_actor_with(ActorOptions::supervised(
+            factory(starts.clone()),
+            SupervisionStrategy::RestartWithBackoff {
+                min: Duration::from_secs(2),
+                max: Duration::from_secs(2),
+            },
+        ))
+        .await?;
+
+    address.tell(Crash)?;
+    // Let the actor panic and start backing off.
+    sleep(Duration::from_millis(10)).await;
+    let shutdown = Instant::now();
+    system.shutdown().await?;
+    assert!(shutdown.elapsed() < Duration::from_secs(1));
+    assert_eq!(1, starts.load(Ordering::SeqCst));
+    Ok(())
+}
+
+#[tokio::test]
+async fn restart_limit_stops_actor() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let address = system
+        .add_actor_with(ActorOptions::supervised(
+            factory(starts.clone()),
+            SupervisionStrategy::RestartWithLimit {
+                max_restarts: 2,
+                within: Duration::from_secs(60),
+            },
+        ))
+        .await?;
+
+    for _ in 0..3 {
+        address.tell(Crash)?;
+    }
+    let result = address.ask(Count).await;
+    assert!(matches!(
+        result,
+        Err(AddressError::ActorStopped) | Err(AddressError::NoResponse)
+    ));
+    assert_eq!(3, starts.load(Ordering::SeqCst));
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn restart_limit_window_expires() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let address = system
+        .add_actor_with(ActorOptions::supervised(
+            factory(starts.clone()),
+            SupervisionStrategy::RestartWithLimit {
+                max_restarts: 2,
+                within: Duration::from_secs(10),
+            },
+        ))
+        .await?;
+
+    address.tell(Crash)?;
+    address.tell(Crash)?;
+    assert_eq!(0, address.ask(Count).await?);
+
+    // The earlier restarts no longer count once they are `within` ago.
+    sleep(Duration::from_secs(11)).await;
+    address.tell(Crash)?;
+    assert_eq!(0, address.ask(Count).await?);
+    assert_eq!(4, starts.load(Ordering::SeqCst));
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn unsupervised_actor_stops_on_panic() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let address = system.add_actor(factory(starts)()).await?;
+
+    address.tell(Crash)?;
+    let result = address.ask(Count).await;
+    assert!(matches!(
+        result,
+        Err(AddressError::ActorStopped) | Err(AddressError::NoResponse)
+    ));
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+/// Panics as soon as it starts.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Increment)]
+struct Doomed {
+    starts: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Actor for Doomed {
+    async fn started(&mut self, _ctx: &mut Context<Self>) {
+        self.starts.fetch_add(1, Ordering::SeqCst);
+        panic!("failed to start on purpose");
+    }
+}
+
+#[async_trait]
+impl Handler<Increment> for Doomed {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Increment) {}
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn restart_gives_up_on_failed_starts() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let factory_starts = starts.clone();
+    let address = system
+        .add_actor_with(ActorOptions::supervised(
+            move || Doomed {
+                starts: factory_starts.clone(),
+            },
+            SupervisionStrategy::Restart,
+        ))
+        .await?;
+
+    assert_eq!(TerminationReason::SupervisorGaveUp, address.closed().await);
+    // The first start, and each of its replacements.
+    assert_eq!(MAX_FAILED_STARTS + 1, starts.load(Ordering::SeqCst));
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use ::tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use ::yaaf::{prelude::*, ActorAddress};
+
+#[derive(Clone, Debug)]
+struct Count(u32);
+
+#[derive(Clone, Debug)]
+struct Greet(ActorAddress<Client>);
+
+#[derive(Clone, Debug)]
+struct Hello;
+
+#[derive(Clone, Debug)]
+struct Start(ActorAddress<Greeter>);
+
+#[derive(Clone, Debug)]
+struct WhoAmI;
+
+/// Counts down by telling itself the next number.
+#[derive(Actor)]
+#[handle(Count)]
+struct Countdown {
+    counts: UnboundedSender<u32>,
+}
+
+#[async_trait]
+impl Handler<Count> for Countdown {
+    async fn handle(&mut self, ctx: &mut Context<Self>, message: Count) {
+        self.counts.send(message.0).unwrap();
+        if message.0 > 0 {
+            ctx.tell_self(Count(message.0 - 1)).unwrap();
+        }
+    }
+}
+
+/// Replies to whoever greets it.
+#[derive(Actor)]
+#[handle(Greet)]
+struct Greeter;
+
+#[async_trait]
+impl Handler<Greet> for Greeter {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Greet) {
+        message.0.tell(Hello).unwrap();
+    }
+}
+
+/// Greets the greeter with its own address to reply to.
+#[derive(Actor)]
+#[handle(Start, Hello)]
+struct Client {
+    replies: UnboundedSender<()>,
+}
+
+#[async_trait]
+impl Handler<Start> for Client {
+    async fn handle(&mut self, ctx: &mut Context<Self>, message: Start) {
+        message.0.tell(Greet(ctx.address())).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Hello> for Client {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Hello) {
+        self.replies.send(()).unwrap();
+    }
+}
+
+#[async_trait]
+impl Responder<WhoAmI> for Client {
+    type Response = ActorAddress<Client>;
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, _message: WhoAmI) -> Self::Response {
+        ctx.address()
+    }
+}
+
+#[tokio::test]
+async fn tell_self() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut counts) = unbounded_channel();
+    let countdown = system.add_actor(Countdown { counts: send }).await?;
+
+    countdown.tell(Count(3))?;
+    for expected in (0..=3).rev() {
+        assert_eq!(expected, counts.recv().await.unwrap());
+    }
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn address_as_reply_to() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let greeter = system.add_actor(Greeter).await?;
+    let (send, mut replies) = unbounded_channel();
+    let client = system.add_actor(Client { replies: send }).await?;
+
+    assert_eq!(client, client.ask(WhoAmI).await?);
+    client.tell(Start(greeter))?;
+    replies.recv().await.unwrap();
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use ::std::time::Duration;
+use ::tokio::{
+    sync::mpsc::{error::TryRecvError, unbounded_channel, UnboundedSender},
+    time::{sleep, Instant},
+};
+use ::yaaf::{prelude::*, ActorOptions, SupervisionStrategy, TimerHandle};
+
+#[derive(Clone, Debug)]
+struct Tick(u32);
+
+#[derive(Clone, Debug)]
+struct Ping;
+
+#[derive(Clone, Debug)]
+struct After(Duration);
+
+#[derive(Clone, Debug)]
+struct Every(Duration);
+
+#[derive(Clone, Debug)]
+struct Cancel;
+
+#[derive(Clone, Debug)]
+struct PingAt(Instant);
+
+#[derive(Clone, Debug)]
+struct Crash;
+
+/// Starts timers when told to, and reports the ticks they deliver.
+#[derive(Actor)]
+#[publish(Ping)]
+#[handle(Tick)]
+struct Scheduler {
+    ticks: UnboundedSender<(u32, Instant)>,
+    timer: Option<TimerHandle>,
+}
+
+#[async_trait]
+impl Handler<Tick> for Scheduler {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Tick) {
+        self.ticks.send((message.0, Instant::now())).unwrap();
+    }
+}
+
+#[async_trait]
+impl Responder<After> for Scheduler {
+    type Response = ();
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, message: After) {
+        ctx.tell_self_after(message.0, Tick(0));
+    }
+}
+
+#[async_trait]
+impl Responder<Every> for Scheduler {
+    type Response = ();
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, message: Every) {
+        let mut count = 0;
+        self.timer = Some(ctx.run_interval(message.0, move || {
+            count += 1;
+            Tick(count)
+        }));
+    }
+}
+
+#[async_trait]
+impl Responder<Cancel> for Scheduler {
+    type Response = ();
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Cancel) {
+        if let Some(timer) = self.timer.take() {
+            timer.cancel();
+        }
+    }
+}
+
+#[async_trait]
+impl Responder<PingAt> for Scheduler {
+    type Response = ();
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, message: PingAt) {
+        ctx.publish_at(message.0, Ping).unwrap();
+    }
+}
+
+#[derive(Actor)]
+#[handle(Ping)]
+struct Listener {
+    pings: UnboundedSender<Instant>,
+}
+
+#[async_trait]
+impl Handler<Ping> for Listener {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Ping) {
+        self.pings.send(Instant::now()).unwrap();
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn tell_self_after_delay() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut ticks) = unbounded_channel();
+    let scheduler = system
+        .add_actor(Scheduler {
+            ticks: send,
+            timer: None,
+        })
+        .await?;
+
+    let start = Instant::now();
+    scheduler.ask(After(Duration::from_secs(5))).await?;
+    let (tick, at) = ticks.recv().await.unwrap();
+    assert_eq!(0, tick);
+    assert!(at - start >= Duration::from_secs(5));
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn interval_until_cancelled() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut ticks) = unbounded_channel();
+    let scheduler = system
+        .add_actor(Scheduler {
+            ticks: send,
+            timer: None,
+        })
+        .await?;
+
+    let start = Instant::now();
+    scheduler.ask(Every(Duration::from_secs(1))).await?;
+    for i in 1..=3 {
+        let (tick, at) = ticks.recv().await.unwrap();
+        assert_eq!(i, tick);
+        assert_eq!(Duration::from_secs(i.into()), at - start);
+    }
+
+    scheduler.ask(Cancel).await?;
+    sleep(Duration::from_secs(10)).await;
+    assert_eq!(
+        Err(TryRecvError::Empty),
+        ticks.try_recv().map(|(tick, _)| tick)
+    );
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn publish_at_instant() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, _ticks) = unbounded_channel();
+    let scheduler = system
+        .add_actor(Scheduler {
+            ticks: send,
+            timer: None,
+        })
+        .await?;
+    let (send, mut pings) = unbounded_channel();
+    system.add_actor(Listener { pings: send }).await?;
+
+    let at = Instant::now() + Duration::from_secs(30);
+    scheduler.ask(PingAt(at)).await?;
+    assert!(pings.recv().await.unwrap() >= at);
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn timers_stop_with_actor() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, _ticks) = unbounded_channel();
+    let scheduler = system
+        .add_actor(Scheduler {
+            ticks: send,
+            timer: None,
+        })
+        .await?;
+    let (send, mut pings) = unbounded_channel();
+    system.add_actor(Listener { pings: send }).await?;
+
+    scheduler
+        .ask(PingAt(Instant::now() + Duration::from_secs(30)))
+        .await?;
+    scheduler.stop()?;
+    scheduler.closed().await;
+
+    sleep(Duration::from_secs(60)).await;
+    assert_eq!(Err(TryRecvError::Empty), pings.try_recv());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+/// Ticks every second from the moment it starts.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Tick, Crash)]
+struct Metronome {
+    ticks: UnboundedSender<(u32, Instant)>,
+}
+
+#[async_trait]
+impl Actor for Metronome {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        let mut count = 0;
+        ctx.run_interval(Duration::from_secs(1), move || {
+            count += 1;
+            Tick(count)
+        });
+    }
+}
+
+#[async_trait]
+impl Handler<Tick> for Metronome {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Tick) {
+        self.ticks.send((message.0, Instant::now())).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Crash> for Metronome {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Crash) {
+        panic!("crashed on purpose");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn timers_stop_on_restart() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut ticks) = unbounded_channel();
+    let metronome = system
+        .add_actor_with(ActorOptions::supervised(
+            move || Metronome {
+                ticks: send.clone(),
+            },
+            SupervisionStrategy::Restart,
+        ))
+        .await?;
+
+    sleep(Duration::from_millis(5500)).await;
+    metronome.tell(Crash)?;
+    sleep(Duration::from_millis(4750)).await;
+
+    // Five ticks before the restart, and four from the new instance's own
+    // timer after it.
+    let mut counts = Vec::new();
+    while let Ok((tick, _)) = ticks.try_recv() {
+        counts.push(tick);
+    }
+    assert_eq!(vec![1, 2, 3, 4, 5, 1, 2, 3, 4], counts);
+
+    system.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,312 @@
+use ::tokio::sync::mpsc::{error::TryRecvError, unbounded_channel, UnboundedSender};
+use ::yaaf::{
+    error::{SystemError, YaafInternalError},
+    prelude::*,
+    ActorOptions, SupervisionStrategy,
+};
+
+#[derive(Clone, Debug)]
+struct Order {
+    id: u32,
+    customer: &'static str,
+    amount: u32,
+}
+
+fn order(id: u32, customer: &'static str, amount: u32) -> Order {
+    Order {
+        id,
+        customer,
+        amount,
+    }
+}
+
+fn is_large(order: &Order) -> bool {
+    order.amount >= 100
+}
+
+/// Receives only European orders.
+#[derive(Actor)]
+#[handle(Order, topic = "eu")]
+struct EuDesk {
+    orders: UnboundedSender<u32>,
+}
+
+#[async_trait]
+impl Handler<Order> for EuDesk {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Order) {
+        self.orders.send(message.id).unwrap();
+    }
+}
+
+/// Receives orders published without a topic.
+#[derive(Actor)]
+#[handle(Order)]
+struct Auditor {
+    orders: UnboundedSender<u32>,
+}
+
+#[async_trait]
+impl Handler<Order> for Auditor {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Order) {
+        self.orders.send(message.id).unwrap();
+    }
+}
+
+/// Receives only large European or American orders.
+#[derive(Actor)]
+#[handle(Order, topic = "eu", topic = "us", filter = "is_large")]
+struct Escalations {
+    orders: UnboundedSender<u32>,
+}
+
+#[async_trait]
+impl Handler<Order> for Escalations {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Order) {
+        self.orders.send(message.id).unwrap();
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Ready;
+
+/// Receives orders for VIPs, and subscribes to the orders of one customer
+/// when it starts.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Order, topic = "vip")]
+struct Customer {
+    name: &'static str,
+    orders: UnboundedSender<u32>,
+}
+
+#[async_trait]
+impl Actor for Customer {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.subscribe_to::<Order>(self.name).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Order> for Customer {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Order) {
+        self.orders.send(message.id).unwrap();
+    }
+}
+
+#[async_trait]
+impl Responder<Ready> for Customer {
+    type Response = ();
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Ready) {}
+}
+
+#[derive(Clone, Debug)]
+struct Crash;
+
+/// Like [`Customer`], but panics when told to.
+#[derive(Actor)]
+#[actor(hooks)]
+#[handle(Order, topic = "vip")]
+#[handle(Crash)]
+struct FragileCustomer {
+    name: &'static str,
+    orders: UnboundedSender<u32>,
+}
+
+#[async_trait]
+impl Actor for FragileCustomer {
+    async fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.subscribe_to::<Order>(self.name).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Order> for FragileCustomer {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, message: Order) {
+        self.orders.send(message.id).unwrap();
+    }
+}
+
+#[async_trait]
+impl Handler<Crash> for FragileCustomer {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Crash) {
+        panic!("crashed on purpose");
+    }
+}
+
+#[async_trait]
+impl Responder<Ready> for FragileCustomer {
+    type Response = ();
+
+    async fn respond(&mut self, _ctx: &mut Context<Self>, _message: Ready) {}
+}
+
+/// Republishes every order to the topic of its customer.
+#[derive(Actor)]
+#[handle(Order)]
+#[publish(Order)]
+struct Router;
+
+#[async_trait]
+impl Handler<Order> for Router {
+    async fn handle(&mut self, ctx: &mut Context<Self>, message: Order) {
+        let _ = ctx.publish_to(message.customer, message);
+    }
+}
+
+#[tokio::test]
+async fn topics_partition_messages() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut eu) = unbounded_channel();
+    system.add_actor(EuDesk { orders: send }).await?;
+    let (send, mut all) = unbounded_channel();
+    system.add_actor(Auditor { orders: send }).await?;
+
+    system.publish_to("eu", order(1, "alice", 10))?;
+    system.publish(order(2, "bob", 10))?;
+    system.publish_to("eu", order(3, "alice", 10))?;
+    assert!(matches!(
+        system.publish_to("us", order(4, "bob", 10)),
+        Err(SystemError::PublishFailure { .. })
+    ));
+
+    assert_eq!(1, eu.recv().await.unwrap());
+    assert_eq!(3, eu.recv().await.unwrap());
+    assert_eq!(2, all.recv().await.unwrap());
+    system.publish(order(5, "bob", 10))?;
+    assert_eq!(5, all.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn filter_drops_messages() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut escalations) = unbounded_channel();
+    system.add_actor(Escalations { orders: send }).await?;
+
+    system.publish_to("eu", order(1, "alice", 10))?;
+    system.publish_to("us", order(2, "bob", 500))?;
+    system.publish_to("eu", order(3, "alice", 100))?;
+
+    let mut received = vec![
+        escalations.recv().await.unwrap(),
+        escalations.recv().await.unwrap(),
+    ];
+    received.sort_unstable();
+    assert_eq!(vec![2, 3], received);
+
+    system.publish_to("eu", order(4, "alice", 1000))?;
+    assert_eq!(4, escalations.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn subscribe_to_topic_at_runtime() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut alice) = unbounded_channel();
+    let alice_address = system
+        .add_actor(Customer {
+            name: "alice",
+            orders: send,
+        })
+        .await?;
+    let (send, mut bob) = unbounded_channel();
+    let bob_address = system
+        .add_actor(Customer {
+            name: "bob",
+            orders: send,
+        })
+        .await?;
+    system.add_actor(Router).await?;
+    // Both have subscribed once they have started.
+    alice_address.ask(Ready).await?;
+    bob_address.ask(Ready).await?;
+
+    system.publish(order(1, "alice", 10))?;
+    system.publish(order(2, "bob", 10))?;
+    system.publish(order(3, "alice", 10))?;
+
+    assert_eq!(1, alice.recv().await.unwrap());
+    assert_eq!(3, alice.recv().await.unwrap());
+    assert_eq!(2, bob.recv().await.unwrap());
+    system.publish(order(4, "bob", 10))?;
+    assert_eq!(4, bob.recv().await.unwrap());
+
+    system.publish_to("vip", order(5, "carol", 10))?;
+    assert_eq!(5, alice.recv().await.unwrap());
+    assert_eq!(5, bob.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn topic_without_subscribers() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, _eu) = unbounded_channel();
+    let desk = system.add_actor(EuDesk { orders: send }).await?;
+    system.publish_to("eu", order(1, "alice", 10))?;
+
+    desk.stop()?;
+    desk.closed().await;
+
+    // Once its subscribers have stopped, a topic fails like a message type
+    // nobody subscribes to.
+    assert!(matches!(
+        system.publish(order(2, "bob", 10)),
+        Err(SystemError::PublishFailure {
+            source: YaafInternalError::SendFailure
+        })
+    ));
+    for _ in 0..2 {
+        assert!(matches!(
+            system.publish_to("eu", order(3, "alice", 10)),
+            Err(SystemError::PublishFailure {
+                source: YaafInternalError::SendFailure
+            })
+        ));
+    }
+
+    // The topic can be subscribed to again.
+    let (send, mut eu) = unbounded_channel();
+    system.add_actor(EuDesk { orders: send }).await?;
+    system.publish_to("eu", order(4, "alice", 10))?;
+    assert_eq!(4, eu.recv().await.unwrap());
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn restart_drops_runtime_subscriptions() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+    let (send, mut orders) = unbounded_channel();
+    let customer = system
+        .add_actor_with(ActorOptions::supervised(
+            move || FragileCustomer {
+                name: "alice",
+                orders: send.clone(),
+            },
+            SupervisionStrategy::Restart,
+        ))
+        .await?;
+
+    customer.tell(Crash)?;
+    // The replacement has subscribed again once it answers.
+    customer.ask(Ready).await?;
+
+    system.publish_to("alice", order(7, "alice", 10))?;
+    system.publish_to("vip", order(8, "alice", 10))?;
+    let mut received = vec![orders.recv().await.unwrap(), orders.recv().await.unwrap()];
+    received.sort_unstable();
+    assert_eq!(vec![7, 8], received);
+    customer.ask(Ready).await?;
+    assert_eq!(Err(TryRecvError::Empty), orders.try_recv());
+
+    system.shutdown().await?;
+    Ok(())
+}
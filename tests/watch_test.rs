@@ -0,0 +1,105 @@
+use ::tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use ::yaaf::{
+    prelude::*, ActorAddress, ActorOptions, SupervisionStrategy, Terminated, TerminationReason,
+};
+
+#[derive(Clone, Debug)]
+struct Crash;
+
+#[derive(Actor, Debug)]
+#[handle(Crash)]
+struct Worker;
+
+#[async_trait]
+impl Handler<Crash> for Worker {
+    async fn handle(&mut self, _ctx: &mut Context<Self>, _message: Crash) {
+        panic!("crashed on purpose");
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Watch(ActorAddress<Worker>);
+
+#[derive(Actor)]
+#[actor(hooks)]
+struct Coordinator {
+    terminated: UnboundedSender<Terminated>,
+}
+
+#[async_trait]
+impl Actor for Coordinator {
+    async fn terminated(&mut self, _ctx: &mut Context<Self>, terminated: Terminated) {
+        self.terminated.send(terminated).unwrap();
+    }
+}
+
+#[async_trait]
+impl Responder<Watch> for Coordinator {
+    type Response = ();
+
+    async fn respond(&mut self, ctx: &mut Context<Self>, message: Watch) {
+        ctx.watch(&message.0);
+    }
+}
+
+#[tokio::test]
+async fn watcher_is_told_about_termination() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let (terminated, mut recv_terminated) = unbounded_channel();
+    let coordinator = system.add_actor(Coordinator { terminated }).await?;
+    let crashing = system.add_actor(Worker).await?;
+    let stopping = system.add_actor(Worker).await?;
+
+    coordinator.ask(Watch(crashing.clone())).await?;
+    crashing.tell(Crash)?;
+    assert_eq!(
+        Terminated {
+            id: crashing.id(),
+            reason: TerminationReason::Panicked,
+        },
+        recv_terminated.recv().await.unwrap()
+    );
+
+    coordinator.ask(Watch(stopping.clone())).await?;
+    stopping.stop()?;
+    assert_eq!(
+        Terminated {
+            id: stopping.id(),
+            reason: TerminationReason::Stopped,
+        },
+        recv_terminated.recv().await.unwrap()
+    );
+
+    // Watching an actor that has already terminated reports it at once.
+    coordinator.ask(Watch(crashing.clone())).await?;
+    assert_eq!(
+        Terminated {
+            id: crashing.id(),
+            reason: TerminationReason::Panicked,
+        },
+        recv_terminated.recv().await.unwrap()
+    );
+
+    system.shutdown().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn closed_reports_reason() -> Result<(), Box<dyn ::std::error::Error>> {
+    let mut system = System::new();
+
+    let gave_up = system
+        .add_actor_with(ActorOptions::supervised(
+            || Worker,
+            SupervisionStrategy::Stop,
+        ))
+        .await?;
+    gave_up.tell(Crash)?;
+    assert_eq!(TerminationReason::SupervisorGaveUp, gave_up.closed().await);
+
+    let running = system.add_actor(Worker).await?;
+    system.shutdown().await?;
+    assert_eq!(TerminationReason::Shutdown, running.closed().await);
+    Ok(())
+}